@@ -1,6 +1,7 @@
 use anyhow::Result;
 use ink::env::Environment;
 use ink_e2e::{
+    build_message,
     subxt::{
         config::ExtrinsicParams,
         ext::{sp_core::sr25519, sp_runtime},
@@ -33,9 +34,11 @@ where
     E: Environment,
 {
     client: Client<C, E>,
+    /// 所连接链的创世区块哈希,部署 EntryPoint 时作为链标识。
+    chain_id: ink::primitives::Hash,
 }
 
-const CONTRACTS: [&str; 8] = [
+const CONTRACTS: [&str; 9] = [
     "./output/base_account.wasm",
     "./output/base_paymaster.wasm",
     "./output/entry_point.wasm",
@@ -43,6 +46,7 @@ const CONTRACTS: [&str; 8] = [
     "./output/stake_mananer.wasm",
     "./output/nonce_manager.wasm",
     "./output/recover_sig.wasm",
+    "./output/sender_creator.wasm",
     "./output/simple_paymaster.wasm",
 ];
 
@@ -60,8 +64,9 @@ where
 {
     async fn new(ws_url: impl AsRef<str>) -> Result<Self> {
         let online_client = OnlineClient::<C>::from_url(ws_url).await?;
+        let chain_id = online_client.genesis_hash().as_ref().try_into()?;
         let client = Client::<C, E>::new(online_client, CONTRACTS).await;
-        Ok(Self { client })
+        Ok(Self { client, chain_id })
     }
 }
 
@@ -82,10 +87,14 @@ where
 
         let stake_manager_code_hash = client.upload("stake_manager", &signer, None).await?;
 
+        let sender_creator_code_hash = client.upload("sender_creator", &signer, None).await?;
+
         let constructor = entry_point::EntryPointRef::new(
             12,
             stake_manager_code_hash.code_hash,
             nonce_manager_code_hash.code_hash,
+            sender_creator_code_hash.code_hash,
+            self.chain_id,
         );
 
         let entry_point_contract = client
@@ -138,6 +147,10 @@ where
             .instantiate("base_account", &signer, base_account_constructor, 0, None)
             .await?;
 
+        let set_account = build_message::<recover_sig::RecoverSigRef>(wallet_contract.account_id)
+            .call(|wallet| wallet.set_account(base_account_contract.account_id));
+        client.call(&signer, set_account, 0, None).await?;
+
         Ok(())
     }
 }
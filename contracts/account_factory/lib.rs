@@ -0,0 +1,165 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::account_factory::AccountFactoryRef;
+
+/// 账户工厂。
+///
+/// 使用确定的 salt 创建 `base_account` 以及它的 `recover_sig` 验证器,
+/// 因此账户地址在部署之前就可以计算出来。
+/// `UserOperation::init_code` 为工厂地址 + `create_account` 的选择器 + 参数。
+#[ink::contract(env = ink_aa::core::env::AAEnvironment)]
+mod account_factory {
+    use base_account::BaseAccountRef;
+    use ink::{env::hash::Blake2x256, prelude::vec::Vec, ToAccountId};
+    use recover_sig::RecoverSigRef;
+    use scale::Encode;
+
+    #[ink(storage)]
+    pub struct AccountFactory {
+        entry_point: AccountId,
+        account_code_hash: Hash,
+        validator_code_hash: Hash,
+    }
+
+    impl AccountFactory {
+        #[ink(constructor)]
+        pub fn new(
+            entry_point: AccountId,
+            account_code_hash: Hash,
+            validator_code_hash: Hash,
+        ) -> Self {
+            Self {
+                entry_point,
+                account_code_hash,
+                validator_code_hash,
+            }
+        }
+
+        /// 创建账户并返回账户地址。
+        /// 如果账户已经存在,则直接返回账户地址,
+        /// 这样在 UserOperation 执行后 `get_sender_address` 仍然可以正常工作。
+        ///
+        /// - `requirement` 所需的签名数量
+        /// - `owners` 账户所有者
+        /// - `salt` 用于计算账户地址的 salt
+        #[ink(message)]
+        pub fn create_account(
+            &mut self,
+            requirement: u32,
+            owners: Vec<AccountId>,
+            salt: [u8; 32],
+        ) -> AccountId {
+            let (account, _) = self.inner_get_address(requirement, &owners, salt);
+            if self.env().is_contract(&account) {
+                return account;
+            }
+
+            let mut validator = RecoverSigRef::new(requirement, owners)
+                .endowment(0)
+                .code_hash(self.validator_code_hash)
+                .salt_bytes(salt)
+                .instantiate();
+
+            let account = BaseAccountRef::new(self.entry_point, validator.to_account_id())
+                .endowment(0)
+                .code_hash(self.account_code_hash)
+                .salt_bytes(salt)
+                .instantiate();
+            let account = account.to_account_id();
+            // 工厂是验证器的部署者,由它将验证器绑定到账户。
+            validator.set_account(account);
+            account
+        }
+
+        /// 计算 `create_account` 将会创建的账户地址。
+        #[ink(message)]
+        pub fn get_address(
+            &self,
+            requirement: u32,
+            owners: Vec<AccountId>,
+            salt: [u8; 32],
+        ) -> AccountId {
+            self.inner_get_address(requirement, &owners, salt).0
+        }
+
+        /// 返回 (账户地址, 验证器地址)。
+        fn inner_get_address(
+            &self,
+            requirement: u32,
+            owners: &Vec<AccountId>,
+            salt: [u8; 32],
+        ) -> (AccountId, AccountId) {
+            let deployer = self.env().account_id();
+
+            let mut input = ink::selector_bytes!("new").to_vec();
+            (requirement, owners).encode_to(&mut input);
+            let validator = contract_address(&deployer, &self.validator_code_hash, &input, &salt);
+
+            let mut input = ink::selector_bytes!("new").to_vec();
+            (self.entry_point, validator).encode_to(&mut input);
+            let account = contract_address(&deployer, &self.account_code_hash, &input, &salt);
+
+            (account, validator)
+        }
+    }
+
+    /// 按照 pallet-contracts 的 `DefaultAddressGenerator` 计算合约地址:
+    /// `blake2_256(b"contract_addr_v1" ++ deployer ++ code_hash ++ input_data ++ salt)`,
+    /// 其中 `input_data` 和 `salt` 按 SCALE 编码,带有长度前缀。
+    fn contract_address(
+        deployer: &AccountId,
+        code_hash: &Hash,
+        input_data: &[u8],
+        salt: &[u8],
+    ) -> AccountId {
+        let mut output = [0u8; 32];
+        ink::env::hash_encoded::<Blake2x256, _>(
+            &(b"contract_addr_v1", deployer, code_hash, input_data, salt),
+            &mut output,
+        );
+        AccountId::from(output)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink_aa::core::env::AAEnvironment;
+
+        #[test]
+        fn contract_address_matches_pallet_contracts() {
+            let input = [3u8, 4];
+            let salt = [5u8; 32];
+            // blake2_256(b"contract_addr_v1" ++ [1; 32] ++ [2; 32]
+            //     ++ compact(2) ++ [3, 4] ++ compact(32) ++ [5; 32])
+            let expected = [
+                0x11, 0x58, 0x5f, 0x8c, 0x3d, 0x7e, 0x92, 0xc3, 0x05, 0x8f, 0xc5, 0x8c, 0xed, 0x03,
+                0x09, 0xd2, 0x4b, 0xeb, 0x36, 0x68, 0x1e, 0xd4, 0x22, 0x09, 0xa7, 0x4b, 0x47, 0x77,
+                0x12, 0xd6, 0xfa, 0x6b,
+            ];
+            assert_eq!(
+                contract_address(
+                    &AccountId::from([1; 32]),
+                    &Hash::from([2; 32]),
+                    &input,
+                    &salt
+                ),
+                AccountId::from(expected)
+            );
+        }
+
+        #[ink::test]
+        fn address_depends_on_owners_and_salt() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let factory =
+                AccountFactory::new(accounts.alice, Hash::from([1; 32]), Hash::from([2; 32]));
+            let address = factory.get_address(1, vec![accounts.bob], [0; 32]);
+
+            assert_eq!(address, factory.get_address(1, vec![accounts.bob], [0; 32]));
+            assert_ne!(address, factory.get_address(1, vec![accounts.bob], [1; 32]));
+            assert_ne!(
+                address,
+                factory.get_address(1, vec![accounts.charlie], [0; 32])
+            );
+        }
+    }
+}
@@ -1,19 +1,21 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-pub use base_account::{BaseAccountRef, BaseAccountTrait};
+pub use self::base_account::{BaseAccountRef, BaseAccountTrait};
 
 #[ink::contract(env = ink_aa::core::env::AAEnvironment)]
 mod base_account {
+    use ink::prelude::vec::Vec;
     use ink_aa::{
         core::{
             env::AAEnvironment,
             error::{Error, Result},
+            exec::{call_output, OpaqueTypes, Transaction},
             helpers::ValidationData,
             user_operation::UserOperation,
         },
         traits::{
-            account::IAccount,
-            entry_point::{EntryPointRef, NonceManagerRef, StakeManagerRef},
+            account::{IAccount, IAccountExecute, IAggregatedAccount},
+            entry_point::{AggregatedAccountRef, EntryPointRef, NonceManagerRef, StakeManagerRef},
             nonce_manager::INonceManager,
         },
     };
@@ -67,6 +69,10 @@ mod base_account {
             self.advanced.into()
         }
 
+        pub fn aggregated_ref(&self) -> AggregatedAccountRef<AAEnvironment> {
+            self.advanced.into()
+        }
+
         pub fn stake_manager_ref(&self) -> StakeManagerRef<AAEnvironment> {
             self.entry_point.into()
         }
@@ -94,7 +100,7 @@ mod base_account {
             missing_account_funds: Balance,
         ) -> Result<ValidationData<AAEnvironment>> {
             self.inner_require_from_entry_point()?;
-            let advanced = self.base_ref();
+            let mut advanced = self.base_ref();
             let nonce = user_op.nonce;
             let validation_data = advanced.validate_signature(user_op, user_op_hash)?;
             advanced.validate_nonce(nonce)?;
@@ -103,6 +109,57 @@ mod base_account {
         }
     }
 
+    /// 转发给 `advanced`,只有使用签名聚合器的验证器才实现了它。
+    impl IAggregatedAccount for BaseAccount {
+        #[ink(message)]
+        fn get_aggregator(&self) -> AccountId {
+            self.aggregated_ref().get_aggregator()
+        }
+
+        #[ink(message)]
+        fn get_public_key(&self) -> Vec<u8> {
+            self.aggregated_ref().get_public_key()
+        }
+    }
+
+    impl IAccountExecute for BaseAccount {
+        #[ink(message)]
+        fn execute(
+            &mut self,
+            callee: AccountId,
+            selector: [u8; 4],
+            input: Vec<u8>,
+            value: Balance,
+        ) -> Result<Vec<u8>> {
+            self.inner_require_from_entry_point()?;
+            // gas_limit 为 0 表示使用 EntryPoint 转发过来的全部剩余 gas。
+            let transaction = Transaction::<AAEnvironment>::new(callee, selector, input, value, 0);
+            execution_output(0, transaction.try_invoke())
+        }
+
+        #[ink(message)]
+        fn execute_batch(
+            &mut self,
+            transactions: Vec<Transaction<AAEnvironment>>,
+        ) -> Result<Vec<Vec<u8>>> {
+            self.inner_require_from_entry_point()?;
+            let mut results = Vec::with_capacity(transactions.len());
+            for (index, transaction) in transactions.into_iter().enumerate() {
+                let result = execution_output(index as u32, transaction.try_invoke())?;
+                results.push(result);
+            }
+            Ok(results)
+        }
+    }
+
+    /// 第 `index` 笔交易的返回值,被调用合约回滚时返回 `Error::ExecutionFailed`。
+    fn execution_output(
+        index: u32,
+        result: ink::env::Result<ink::MessageResult<OpaqueTypes>>,
+    ) -> Result<Vec<u8>> {
+        call_output(result).ok_or(Error::ExecutionFailed { index })
+    }
+
     #[ink::trait_definition]
     pub trait BaseAccountTrait {
         /// 执行一批 UserOperation。
@@ -113,7 +170,7 @@ mod base_account {
         /// - `beneficiary` 用于接收费用的地址
         #[ink(message, payable)]
         fn validate_signature(
-            &self,
+            &mut self,
             op: UserOperation<AAEnvironment>,
             user_op_hash: Hash,
         ) -> Result<ValidationData<AAEnvironment>>;
@@ -121,4 +178,43 @@ mod base_account {
         #[ink(message)]
         fn validate_nonce(&self, nonce: [u8; 32]) -> Result<()>;
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn execute_requires_entry_point() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut account = BaseAccount::new(accounts.alice, accounts.bob);
+
+            ink::env::test::set_caller::<AAEnvironment>(accounts.charlie);
+            assert_eq!(
+                account.execute(accounts.django, [0; 4], Vec::new(), 0),
+                Err(Error::NotFromEntryPoint)
+            );
+            assert_eq!(
+                account.execute_batch(Vec::new()),
+                Err(Error::NotFromEntryPoint)
+            );
+
+            ink::env::test::set_caller::<AAEnvironment>(accounts.alice);
+            assert_eq!(account.execute_batch(Vec::new()), Ok(Vec::new()));
+        }
+
+        #[ink::test]
+        fn callee_returning_true_succeeds() {
+            use scale::Encode;
+
+            // 返回 `true` 的消息编码为 1,不能被当作回滚。
+            assert_eq!(
+                execution_output(0, Ok(Ok(OpaqueTypes(true.encode())))),
+                Ok(vec![1])
+            );
+            assert_eq!(
+                execution_output(2, Err(ink::env::Error::CalleeReverted)),
+                Err(Error::ExecutionFailed { index: 2 })
+            );
+        }
+    }
 }
@@ -1,6 +1,6 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-pub use base_paymaster::{BasePaymasterRef, BasePaymasterTrait};
+pub use self::base_paymaster::{BasePaymasterRef, BasePaymasterTrait};
 
 #[ink::contract(env = ink_aa::core::env::AAEnvironment)]
 mod base_paymaster {
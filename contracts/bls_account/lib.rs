@@ -0,0 +1,95 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::bls_account::{BlsAccount, BlsAccountRef, PUBLIC_KEY_LEN};
+
+/// BLS 验证器。
+///
+/// 作为 `BaseAccount` 的 `advanced` 使用,保存一个 BLS12-381 公钥。
+/// 签名由 `bls_aggregator` 统一验证,此合约只返回聚合器的地址。
+#[ink::contract(env = ink_aa::core::env::AAEnvironment)]
+mod bls_account {
+    use base_account::BaseAccountTrait;
+    use ink::prelude::vec::Vec;
+    use ink_aa::core::{
+        env::AAEnvironment,
+        error::Result,
+        helpers::{Aggregator, ValidationData},
+        user_operation::UserOperation,
+    };
+    use ink_aa::traits::account::IAggregatedAccount;
+
+    /// 压缩格式的 G1 公钥长度。
+    pub const PUBLIC_KEY_LEN: usize = 48;
+
+    #[ink(storage)]
+    pub struct BlsAccount {
+        /// 压缩格式的 BLS12-381 G1 公钥。
+        public_key: Vec<u8>,
+        /// 验证签名的聚合器。
+        aggregator: AccountId,
+    }
+
+    impl BlsAccount {
+        #[ink(constructor)]
+        pub fn new(public_key: Vec<u8>, aggregator: AccountId) -> Self {
+            assert_eq!(public_key.len(), PUBLIC_KEY_LEN);
+            Self {
+                public_key,
+                aggregator,
+            }
+        }
+    }
+
+    impl IAggregatedAccount for BlsAccount {
+        #[ink(message)]
+        fn get_aggregator(&self) -> AccountId {
+            self.aggregator
+        }
+
+        #[ink(message)]
+        fn get_public_key(&self) -> Vec<u8> {
+            self.public_key.clone()
+        }
+    }
+
+    impl BaseAccountTrait for BlsAccount {
+        /// 签名由聚合器验证,这里直接返回 `Aggregator::VerifiedBy(aggregator)`,
+        /// EntryPoint 只接受通过 `handle_aggregated_ops` 提交的此账户的操作。
+        #[ink(message, payable)]
+        fn validate_signature(
+            &mut self,
+            _op: UserOperation<AAEnvironment>,
+            _user_op_hash: Hash,
+        ) -> Result<ValidationData<AAEnvironment>> {
+            Ok(ValidationData {
+                aggregator: Aggregator::VerifiedBy(self.aggregator),
+                valid_after: 0,
+                valid_until: Timestamp::MAX,
+            })
+        }
+
+        /// nonce 由 EntryPoint 的 NonceManager 校验,账户不做额外限制。
+        #[ink(message)]
+        fn validate_nonce(&self, _nonce: [u8; 32]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn signature_is_left_to_aggregator() {
+            let aggregator = AccountId::from([9; 32]);
+            let mut account = BlsAccount::new(vec![1; PUBLIC_KEY_LEN], aggregator);
+
+            let data = account
+                .validate_signature(UserOperation::default(), Hash::from([7; 32]))
+                .unwrap();
+            assert_eq!(data.aggregator, Aggregator::VerifiedBy(aggregator));
+            assert_eq!(account.get_aggregator(), aggregator);
+            assert_eq!(account.get_public_key(), vec![1; PUBLIC_KEY_LEN]);
+        }
+    }
+}
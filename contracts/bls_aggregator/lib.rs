@@ -0,0 +1,271 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::bls_aggregator::{BlsAggregator, BlsAggregatorRef};
+
+/// BLS12-381 签名聚合器。
+///
+/// 公钥为 G1 上的点,签名为 G2 上的点(压缩格式分别为 48 和 96 字节)。
+/// 每个用户操作签名的消息为 `keccak256((user_op_hash, public_key, aggregator))`,
+/// 其中 `user_op_hash` 为 EntryPoint 的 `get_user_op_hash`,包含 EntryPoint 地址和链标识,
+/// 公钥用于防止恶意公钥(rogue key)攻击。一批操作的签名相加得到聚合签名,
+/// 验证时只需要一次 multi-Miller loop 和一次最终幂运算,而不是逐个验证签名。
+#[ink::contract(env = ink_aa::core::env::AAEnvironment)]
+mod bls_aggregator {
+    use bls12_381::{
+        hash_to_curve::{ExpandMsgXmd, HashToCurve},
+        multi_miller_loop, G1Affine, G2Affine, G2Prepared, G2Projective, Gt,
+    };
+    use ink::prelude::vec::Vec;
+    use ink_aa::core::{
+        env::AAEnvironment,
+        error::{Error, Result},
+        helpers::keccak256,
+        user_operation::UserOperation,
+    };
+    use ink_aa::traits::{
+        account::IAggregatedAccount, aggregator::IAggregator, entry_point::AggregatedAccountRef,
+    };
+    use scale::Encode;
+
+    /// 哈希到 G2 使用的域分隔标签,与 IETF BLS 签名草案的基本方案一致。
+    const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+    #[ink(storage)]
+    pub struct BlsAggregator {
+        /// 使用此聚合器的 EntryPoint。
+        entry_point: AccountId,
+        /// 与 EntryPoint 的 `get_chain_id` 一致的链标识。
+        chain_id: Hash,
+    }
+
+    impl BlsAggregator {
+        #[ink(constructor)]
+        pub fn new(entry_point: AccountId, chain_id: Hash) -> Self {
+            Self {
+                entry_point,
+                chain_id,
+            }
+        }
+
+        /// 返回 `user_op` 的发送者需要签名的消息。
+        #[ink(message)]
+        pub fn get_user_op_hash(&self, user_op: UserOperation<AAEnvironment>) -> [u8; 32] {
+            let public_key = self.public_key_of(&user_op);
+            self.user_op_message(&user_op, &public_key)
+        }
+
+        /// 用户操作签名的消息。
+        fn user_op_message(
+            &self,
+            user_op: &UserOperation<AAEnvironment>,
+            public_key: &[u8],
+        ) -> [u8; 32] {
+            let user_op_hash = user_op.user_op_hash(&self.entry_point, &self.chain_id);
+            keccak256(&(user_op_hash, public_key, self.env().account_id()).encode())
+        }
+
+        /// 通过 `IAggregatedAccount` 获取发送者的公钥。
+        fn public_key_of(&self, user_op: &UserOperation<AAEnvironment>) -> Vec<u8> {
+            let account: AggregatedAccountRef<AAEnvironment> = user_op.sender.into();
+            account.get_public_key()
+        }
+
+        fn validation_failed(&self) -> Error {
+            Error::SignatureValidationFailed {
+                aggregator: self.env().account_id(),
+            }
+        }
+
+        /// 验证 `signature` 是否为 `user_ops` 的聚合签名。
+        fn verify_user_ops(
+            &self,
+            user_ops: &[UserOperation<AAEnvironment>],
+            signature: &[u8],
+        ) -> Result<()> {
+            let signature = decode_signature(signature).ok_or_else(|| self.validation_failed())?;
+            let mut signed = Vec::with_capacity(user_ops.len());
+            for user_op in user_ops {
+                let public_key = self.public_key_of(user_op);
+                let point =
+                    decode_public_key(&public_key).ok_or_else(|| self.validation_failed())?;
+                let message = self.user_op_message(user_op, &public_key);
+                signed.push((point, hash_to_g2(&message)));
+            }
+            if verify(&signed, &signature) {
+                Ok(())
+            } else {
+                Err(self.validation_failed())
+            }
+        }
+    }
+
+    impl IAggregator for BlsAggregator {
+        #[ink(message)]
+        fn validate_signatures(
+            &self,
+            user_ops: Vec<UserOperation<AAEnvironment>>,
+            signature: Vec<u8>,
+        ) -> Result<()> {
+            self.verify_user_ops(&user_ops, &signature)
+        }
+
+        /// `op.signature` 为单个操作的签名,验证通过后返回空值,
+        /// 签名只在聚合之后的 `UserOpsPerAggregator::signature` 中提交。
+        #[ink(message)]
+        fn validate_user_op_signature(
+            &self,
+            user_op: UserOperation<AAEnvironment>,
+        ) -> Result<Vec<u8>> {
+            self.verify_user_ops(core::slice::from_ref(&user_op), &user_op.signature)?;
+            Ok(Vec::new())
+        }
+
+        /// 将每个 `op.signature` 相加,返回压缩格式的聚合签名。
+        #[ink(message)]
+        fn aggregate_signatures(
+            &self,
+            user_ops: Vec<UserOperation<AAEnvironment>>,
+        ) -> Result<Vec<u8>> {
+            let mut aggregated = G2Projective::identity();
+            for user_op in &user_ops {
+                aggregated +=
+                    decode_signature(&user_op.signature).ok_or_else(|| self.validation_failed())?;
+            }
+            Ok(G2Affine::from(aggregated).to_compressed().to_vec())
+        }
+    }
+
+    fn hash_to_g2(message: &[u8]) -> G2Affine {
+        <G2Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(message, DST)
+            .into()
+    }
+
+    /// 解码压缩格式的公钥,拒绝无穷远点。
+    fn decode_public_key(bytes: &[u8]) -> Option<G1Affine> {
+        let bytes: [u8; 48] = bytes.try_into().ok()?;
+        Option::<G1Affine>::from(G1Affine::from_compressed(&bytes))
+            .filter(|point| !bool::from(point.is_identity()))
+    }
+
+    fn decode_signature(bytes: &[u8]) -> Option<G2Affine> {
+        let bytes: [u8; 96] = bytes.try_into().ok()?;
+        Option::from(G2Affine::from_compressed(&bytes))
+    }
+
+    /// 验证 e(g1, signature) == ∏ e(public_key_i, H(m_i)),
+    /// 即 e(-g1, signature) · ∏ e(public_key_i, H(m_i)) == 1。
+    fn verify(signed: &[(G1Affine, G2Affine)], signature: &G2Affine) -> bool {
+        let mut prepared = Vec::with_capacity(signed.len() + 1);
+        prepared.push((-G1Affine::generator(), G2Prepared::from(*signature)));
+        for (public_key, message) in signed {
+            prepared.push((*public_key, G2Prepared::from(*message)));
+        }
+        let terms: Vec<_> = prepared
+            .iter()
+            .map(|(public_key, message)| (public_key, message))
+            .collect();
+        multi_miller_loop(&terms).final_exponentiation() == Gt::identity()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use bls12_381::Scalar;
+
+        fn key(seed: u64) -> (Scalar, G1Affine) {
+            let secret = Scalar::from(seed);
+            (secret, (G1Affine::generator() * secret).into())
+        }
+
+        fn sign(secret: Scalar, message: &[u8]) -> G2Affine {
+            (hash_to_g2(message) * secret).into()
+        }
+
+        fn aggregate(signatures: &[G2Affine]) -> G2Affine {
+            signatures
+                .iter()
+                .fold(G2Projective::identity(), |sum, signature| sum + signature)
+                .into()
+        }
+
+        #[test]
+        fn aggregate_signature_verifies() {
+            let signed: Vec<_> = (1..=3)
+                .map(|seed| {
+                    let (secret, public_key) = key(seed);
+                    let message = [seed as u8; 32];
+                    (public_key, message, sign(secret, &message))
+                })
+                .collect();
+            let pairs: Vec<_> = signed
+                .iter()
+                .map(|(public_key, message, _)| (*public_key, hash_to_g2(message)))
+                .collect();
+            let signatures: Vec<_> = signed.iter().map(|(_, _, signature)| *signature).collect();
+
+            assert!(verify(&pairs, &aggregate(&signatures)));
+            // 缺少一个签名。
+            assert!(!verify(&pairs, &aggregate(&signatures[1..])));
+            // 公钥与消息不对应。
+            let swapped = vec![(pairs[1].0, pairs[0].1), (pairs[0].0, pairs[1].1), pairs[2]];
+            assert!(!verify(&swapped, &aggregate(&signatures)));
+        }
+
+        #[test]
+        fn malformed_points_are_rejected() {
+            let (_, public_key) = key(1);
+            assert_eq!(
+                decode_public_key(&public_key.to_compressed()),
+                Some(public_key)
+            );
+            assert_eq!(
+                decode_public_key(&G1Affine::identity().to_compressed()),
+                None
+            );
+            assert_eq!(decode_public_key(&[1; 47]), None);
+            assert_eq!(decode_signature(&[0xff; 96]), None);
+        }
+
+        fn aggregator(chain_id: u8) -> BlsAggregator {
+            BlsAggregator::new(AccountId::from([1; 32]), Hash::from([chain_id; 32]))
+        }
+
+        #[ink::test]
+        fn user_op_message_is_bound_to_entry_point_hash() {
+            let op = UserOperation::default();
+            let public_key = key(1).1.to_compressed();
+            let message = aggregator(1).user_op_message(&op, &public_key);
+
+            let user_op_hash = op.user_op_hash(&AccountId::from([1; 32]), &Hash::from([1; 32]));
+            let aggregator_id = ink::env::account_id::<AAEnvironment>();
+            assert_eq!(
+                message,
+                keccak256(&(user_op_hash, &public_key[..], aggregator_id).encode())
+            );
+            // 另一条链上的相同操作需要不同的签名。
+            assert_ne!(aggregator(2).user_op_message(&op, &public_key), message);
+        }
+
+        #[ink::test]
+        fn aggregate_signatures_sums_op_signatures() {
+            let aggregator = aggregator(1);
+            let signatures = [sign(Scalar::from(1), b"a"), sign(Scalar::from(2), b"b")];
+            let ops: Vec<_> = signatures
+                .iter()
+                .map(|signature| UserOperation {
+                    signature: signature.to_compressed().to_vec(),
+                    ..Default::default()
+                })
+                .collect();
+
+            assert_eq!(
+                aggregator.aggregate_signatures(ops),
+                Ok(aggregate(&signatures).to_compressed().to_vec())
+            );
+            assert!(matches!(
+                aggregator.aggregate_signatures(vec![UserOperation::default()]),
+                Err(Error::SignatureValidationFailed { .. })
+            ));
+        }
+    }
+}
@@ -1,24 +1,28 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-pub use entry_point::EntryPointRef;
+pub use self::entry_point::EntryPointRef;
 
 #[ink::contract(env = ink_aa::core::env::AAEnvironment)]
 mod entry_point {
-    use ink::prelude::{format, vec::Vec};
+    use ink::prelude::{boxed::Box, format, vec, vec::Vec};
 
     use ink_aa::{
         core::{
             env::AAEnvironment,
-            error::{Error, Result},
+            error::{Error, FailedOpReason, Result},
             exec::{OpaqueTypes, Transaction},
-            helpers::{Aggregator, ValidationData},
-            user_operation::UserOperation,
+            helpers::{intersect_time_range, Aggregator, ValidationData},
+            user_operation::{UserOperation, UserOperationCall},
         },
         traits::{
-            entry_point::{AggregatorRef, IEntryPoint, PaymasterRef, UserOpsPerAggregator},
+            entry_point::{
+                AccountExecuteRef, AggregatorRef, AggregatorStakeInfo, IEntryPoint, PaymasterRef,
+                ReturnInfo, UserOpsPerAggregator,
+            },
             nonce_manager::INonceManager,
             paymaster::{IPaymaster, PostOpMode},
-            stake_manager::{DepositInfo, IStakeManager},
+            sender_creator::ISenderCreator,
+            stake_manager::{DepositInfo, IStakeManager, StakeInfo},
         },
     };
 
@@ -26,6 +30,9 @@ mod entry_point {
     pub struct EntryPoint {
         stake_manager: stake_manager::StakeManagerRef,
         nonce_manager: nonce_manager::NonceManagerRef,
+        sender_creator: sender_creator::SenderCreatorRef,
+        /// 链的标识,通常为创世区块的哈希,用于区分部署在相同地址的不同链上的 EntryPoint。
+        chain_id: Hash,
     }
 
     // TODO：等`event2.0`合并发布之后，转移到`traits`下
@@ -33,6 +40,8 @@ mod entry_point {
     pub struct UserOperationReturnValue {
         #[ink(topic)]
         pub user_op_hash: Hash,
+        /// 批量执行时交易的序号,单个调用时为 0。
+        pub call_index: u32,
         #[ink(topic)]
         pub success: bool,
         #[ink(topic)]
@@ -105,11 +114,14 @@ mod entry_point {
     }
 
     impl EntryPoint {
+        /// - `chain_id` 链的标识,应为所在链的创世区块哈希
         #[ink(constructor)]
         pub fn new(
             version: u32,
             stake_manager_code_hash: Hash,
             nonce_manager_code_hash: Hash,
+            sender_creator_code_hash: Hash,
+            chain_id: Hash,
         ) -> Self {
             // let total_balance = Self::env().balance();
             let salt = version.to_le_bytes();
@@ -123,16 +135,30 @@ mod entry_point {
                 .code_hash(nonce_manager_code_hash)
                 .salt_bytes(salt)
                 .instantiate();
+            let sender_creator = sender_creator::SenderCreatorRef::new()
+                .endowment(0)
+                .code_hash(sender_creator_code_hash)
+                .salt_bytes(salt)
+                .instantiate();
 
             Self {
                 stake_manager,
                 nonce_manager,
+                sender_creator,
+                chain_id,
             }
         }
+
+        /// 返回构造时设置的链标识。
+        #[ink(message)]
+        pub fn get_chain_id(&self) -> Hash {
+            self.chain_id
+        }
     }
 
-    #[derive(Clone, Default)]
-    struct UserOpInfo {
+    #[derive(Clone, Default, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct UserOpInfo {
         user_op: UserOperation<AAEnvironment>,
         user_op_hash: [u8; 32],
         prefund: u64,
@@ -197,8 +223,6 @@ mod entry_point {
             let user_op = &op_info.user_op;
             let gas_price = self.get_user_op_gas_price(&user_op);
 
-            let paymaster: PaymasterRef<AAEnvironment> =
-                user_op.paymaster_and_data.paymaster().into();
             let refund_address;
             if user_op
                 .paymaster_and_data
@@ -213,10 +237,23 @@ mod entry_point {
                         .checked_mul(gas_price as Balance)
                         .ok_or(Error::GasValuesOverflow)?;
 
-                    if let Err(e) = paymaster.post_op(mode, context.clone(), actual_gas_cost) {
+                    use ink::codegen::TraitCallBuilder;
+                    let paymaster: PaymasterRef<AAEnvironment> =
+                        user_op.paymaster_and_data.paymaster().into();
+                    let result = paymaster
+                        .call()
+                        .post_op(mode, context.clone(), actual_gas_cost)
+                        .gas_limit(user_op.verification_gas_limit)
+                        .try_invoke();
+                    let error = match result {
+                        Ok(Ok(Ok(()))) => None,
+                        Ok(Ok(Err(e))) => Some(e),
+                        _ => Some(Error::Revert),
+                    };
+                    if let Some(e) = error {
                         return Err(Error::FailedOp {
                             op_index,
-                            reason: format!("AA50 postOp reverted: {e:?}"),
+                            reason: FailedOpReason::PostOpReverted(Box::new(e)),
                         });
                     }
                 }
@@ -230,7 +267,7 @@ mod entry_point {
             if op_info.prefund < actual_gas_cost {
                 return Err(Error::FailedOp {
                     op_index,
-                    reason: format!("AA51 prefund below {actual_gas_cost}"),
+                    reason: FailedOpReason::PrefundBelowActualGasCost { actual_gas_cost },
                 });
             }
             let refund = op_info
@@ -260,27 +297,17 @@ mod entry_point {
          * inner function to handle a UserOperation.
          * Must be declared "external" to open a call context, but it can only be called by handleOps.
          */
-        fn inner_handle_op(
+        #[ink(message)]
+        pub fn inner_handle_op(
             &mut self,
-            call_data: &Vec<u8>,
-            op_info: &UserOpInfo,
-            context: &Vec<u8>,
+            call: UserOperationCall<AAEnvironment>,
+            op_info: UserOpInfo,
+            context: Vec<u8>,
         ) -> Result<u64> {
+            if self.env().caller() != self.env().account_id() {
+                return Err(Error::OnlyInternalCall);
+            }
             let pre_gas = self.env().gas_left();
-            // TODO:
-            ink::env::debug_println!(
-                "only internal call caller: {:?} address: {:?}",
-                self.env().caller(),
-                self.env().account_id()
-            );
-            // if self.env().caller() != self.env().account_id() {
-            //     ink::env::debug_println!(
-            //         "only internal call caller: {:?} address: {:?}",
-            //         self.env().caller(),
-            //         self.env().account_id()
-            //     );
-            //     return Err(Error::OnlyInternalCall);
-            // }
 
             let user_op = op_info.user_op.clone();
             let call_gas_limit = user_op.call_gas_limit;
@@ -295,47 +322,167 @@ mod entry_point {
             }
             let mut mode = PostOpMode::OpSucceeded;
 
-            if !call_data.is_empty() {
-                let user_op_hash = user_op.hash();
-                let call = Transaction::<AAEnvironment>::new(
-                    user_op.callee,
-                    user_op.selector,
-                    user_op.call_data,
-                    user_op.call_gas_limit,
-                )
-                .call();
-                match call.try_invoke() {
-                    Ok(Ok(result)) => {
-                        ink::env::debug_println!("call result {:?}", result);
-                        ink::codegen::EmitEvent::<Self>::emit_event(
-                            self.env(),
-                            UserOperationReturnValue {
-                                user_op_hash: user_op_hash.into(),
-                                success: true,
-                                result,
-                            },
-                        );
+            if !call.is_empty() {
+                use ink::codegen::TraitCallBuilder;
+                use ink_aa::traits::account::IAccountExecute;
+
+                let user_op_hash = op_info.user_op_hash;
+                // 由账户发起调用,被调用的合约看到的调用者是账户而不是 EntryPoint。
+                let mut account: AccountExecuteRef<AAEnvironment> = user_op.sender.into();
+                let results = match call {
+                    UserOperationCall::Call {
+                        callee,
+                        selector,
+                        call_data,
+                        value,
+                    } => {
+                        match account
+                            .call_mut()
+                            .execute(callee, selector, call_data, value)
+                            .gas_limit(call_gas_limit)
+                            .try_invoke()
+                        {
+                            Ok(Ok(Ok(result))) => Ok(vec![OpaqueTypes(result)]),
+                            e => Err(format!("{:?}", e)),
+                        }
+                    }
+                    UserOperationCall::Batch(transactions) => {
+                        match account
+                            .call_mut()
+                            .execute_batch(transactions)
+                            .gas_limit(call_gas_limit)
+                            .try_invoke()
+                        {
+                            Ok(Ok(Ok(results))) => {
+                                Ok(results.into_iter().map(OpaqueTypes).collect())
+                            }
+                            e => Err(format!("{:?}", e)),
+                        }
+                    }
+                };
+                match results {
+                    Ok(results) => {
+                        ink::env::debug_println!("call result {:?}", results);
+                        for (call_index, result) in results.into_iter().enumerate() {
+                            ink::codegen::EmitEvent::<Self>::emit_event(
+                                self.env(),
+                                UserOperationReturnValue {
+                                    user_op_hash: user_op_hash.into(),
+                                    call_index: call_index as u32,
+                                    success: true,
+                                    result,
+                                },
+                            );
+                        }
                     }
-                    e => {
-                        ink::env::debug_println!("call error: {:?}", e);
+                    Err(revert_reason) => {
+                        ink::env::debug_println!("call error: {}", revert_reason);
                         ink::codegen::EmitEvent::<Self>::emit_event(
                             self.env(),
                             UserOperationRevertReason {
                                 user_op_hash: op_info.user_op_hash.into(),
                                 sender: user_op.sender,
                                 nonce: user_op.nonce.into(),
-                                revert_reason: format!("{:?}", e).into_bytes(),
+                                revert_reason: revert_reason.into_bytes(),
                             },
                         );
                         mode = PostOpMode::OpReverted;
                     }
-                };
+                }
             }
             let actual_gas = pre_gas
                 .checked_sub(self.env().gas_left())
                 .and_then(|pre| pre.checked_add(op_info.pre_op_gas))
                 .ok_or(Error::GasValuesOverflow)?;
-            self.handle_post_op(0, mode, op_info, context, actual_gas)
+            self.handle_post_op(0, mode, &op_info, &context, actual_gas)
+        }
+
+        /**
+         * 以允许重入的方式调用 EntryPoint 自身的 `inner_handle_op`,
+         * 每个 UserOperation 在独立的调用帧中执行,失败时它的所有状态变化都会被回滚。
+         */
+        fn call_inner_handle_op(
+            &self,
+            call: &UserOperationCall<AAEnvironment>,
+            op_info: &UserOpInfo,
+            context: &Vec<u8>,
+        ) -> Result<u64> {
+            use ink::env::{
+                call::{build_call, ExecutionInput, Selector},
+                CallFlags,
+            };
+            let result = build_call::<AAEnvironment>()
+                .call(self.env().account_id())
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("inner_handle_op")))
+                        .push_arg(call)
+                        .push_arg(op_info)
+                        .push_arg(context),
+                )
+                .returns::<Result<u64>>()
+                .try_invoke();
+            match result {
+                Ok(Ok(result)) => result,
+                _ => Err(Error::Revert),
+            }
+        }
+
+        /**
+         * 验证一个 UserOperation,只能由 `handle_ops` 通过 `call_inner_validate_op` 调用。
+         * 验证失败时返回错误使调用帧回滚,撤销已扣除的押金、已更新的 nonce 和已部署的账户。
+         */
+        #[ink(message)]
+        pub fn inner_validate_op(
+            &mut self,
+            op_index: u64,
+            user_op: UserOperation<AAEnvironment>,
+            expected_aggregator: Aggregator<AAEnvironment>,
+        ) -> Result<UserOpInfo> {
+            if self.env().caller() != self.env().account_id() {
+                return Err(Error::OnlyInternalCall);
+            }
+            let mut op_info = UserOpInfo::default();
+            let (validation_data, pm_validation_data) =
+                self.validate_prepayment(op_index, &user_op, &mut op_info)?;
+            self.validate_account_and_paymaster_validation_data(
+                op_index,
+                validation_data,
+                pm_validation_data,
+                expected_aggregator,
+            )?;
+            Ok(op_info)
+        }
+
+        /**
+         * 以允许重入的方式调用 EntryPoint 自身的 `inner_validate_op`,
+         * 验证失败的 UserOperation 不会在批次中留下任何状态变化。
+         */
+        fn call_inner_validate_op(
+            &self,
+            op_index: u64,
+            user_op: &UserOperation<AAEnvironment>,
+            expected_aggregator: &Aggregator<AAEnvironment>,
+        ) -> Result<UserOpInfo> {
+            use ink::env::{
+                call::{build_call, ExecutionInput, Selector},
+                CallFlags,
+            };
+            let result = build_call::<AAEnvironment>()
+                .call(self.env().account_id())
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("inner_validate_op")))
+                        .push_arg(op_index)
+                        .push_arg(user_op)
+                        .push_arg(expected_aggregator),
+                )
+                .returns::<Result<UserOpInfo>>()
+                .try_invoke();
+            match result {
+                Ok(Ok(result)) => result,
+                _ => Err(Error::Revert),
+            }
         }
 
         /**
@@ -354,10 +501,14 @@ mod entry_point {
             let pre_gas = self.env().gas_left();
             let context = op_info.context.clone();
 
-            let actual_gas_cost = match self.inner_handle_op(&user_op.call_data, op_info, &context)
+            let actual_gas_cost = match self.call_inner_handle_op(&user_op.call, op_info, &context)
             {
                 Ok(actual_gas_cost) => actual_gas_cost,
                 Err(Error::OutOfGas) => return Err(Error::OutOfGas),
+                // 用户调用失败时 inner_handle_op 以 OpReverted 调用 postOp,
+                // 走到这里说明 postOp 本身失败了。以 PostOpReverted 再调用一次,
+                // 保证 paymaster 总能收取费用;这次再失败则整批回滚。
+                // inner_handle_op 的调用帧已被回滚,用户调用的结果也随之撤销。
                 Err(_) => {
                     let actual_gas = pre_gas
                         .checked_sub(self.env().gas_left())
@@ -365,7 +516,7 @@ mod entry_point {
                         .ok_or(Error::GasValuesOverflow)?;
                     self.handle_post_op(
                         op_index,
-                        PostOpMode::OpReverted,
+                        PostOpMode::PostOpReverted,
                         op_info,
                         &context,
                         actual_gas,
@@ -405,30 +556,45 @@ mod entry_point {
                 )?;
             let m_user_op = &mut out_op_info.user_op;
 
-            if !self
-                .nonce_manager
+            self.nonce_manager
                 .validate_and_update_nonce(m_user_op.sender, m_user_op.nonce)
-            {
-                return Err(Error::InvalidAccountNonce);
-            }
-
-            if m_user_op.paymaster_and_data.is_eq_zero() {
-                return Err(Error::InvalidPaymasterAddress);
-            }
+                .map_err(|e| match e {
+                    Error::InvalidAccountNonce => Error::FailedOp {
+                        op_index,
+                        reason: FailedOpReason::InvalidNonce,
+                    },
+                    e => e,
+                })?;
 
-            let (context, paymaster_validation_data) = self.validate_paymaster_prepayment(
-                op_index,
-                user_op,
-                out_op_info,
-                required_pre_fund as Balance,
-                gas_used_by_validate_account_prepayment,
-            )?;
+            // 没有 paymaster 时由账户的押金支付,已在 validate_account_prepayment 中扣除。
+            let (context, paymaster_validation_data) = if m_user_op.paymaster_and_data.is_eq_zero()
+            {
+                (
+                    Vec::new(),
+                    ValidationData {
+                        aggregator: Aggregator::NoAggregator,
+                        valid_after: 0,
+                        valid_until: Timestamp::MAX,
+                    },
+                )
+            } else {
+                self.validate_paymaster_prepayment(
+                    op_index,
+                    user_op,
+                    out_op_info,
+                    required_pre_fund as Balance,
+                    gas_used_by_validate_account_prepayment,
+                )?
+            };
 
             let gas_used = pre_gas
                 .checked_sub(self.env().gas_left())
                 .ok_or(Error::GasValuesOverflow)?;
             if user_op.verification_gas_limit < gas_used {
-                return Err(Error::OverVerificationGasLimit);
+                return Err(Error::FailedOp {
+                    op_index,
+                    reason: FailedOpReason::OverVerificationGasLimit,
+                });
             }
             out_op_info.prefund = required_pre_fund;
             out_op_info.context = context;
@@ -440,21 +606,71 @@ mod entry_point {
         }
 
         fn get_required_prefund(&self, user_op: &UserOperation<AAEnvironment>) -> Result<u64> {
-            let mul = if user_op.paymaster_and_data.is_eq_zero() {
-                3
-            } else {
-                1
+            user_op.required_prefund().ok_or(Error::GasValuesOverflow)
+        }
+
+        /**
+         * create the sender's contract if needed.
+         * @param initCode the initCode value from a UserOp. contains the factory address followed by the call data.
+         */
+        fn create_sender_if_needed(
+            &mut self,
+            op_index: u64,
+            op_info: &UserOpInfo,
+            init_code: &[u8],
+        ) -> Result<()> {
+            if init_code.is_empty() {
+                return Ok(());
+            }
+            let m_user_op = &op_info.user_op;
+            let sender = m_user_op.sender;
+            if self.env().is_contract(&sender) {
+                return Err(Error::FailedOp {
+                    op_index,
+                    reason: FailedOpReason::SenderAlreadyConstructed,
+                });
+            }
+
+            use ink::codegen::TraitCallBuilder;
+            let sender1 = match self
+                .sender_creator
+                .call_mut()
+                .create_sender(init_code.to_vec())
+                .gas_limit(m_user_op.verification_gas_limit)
+                .try_invoke()
+            {
+                Ok(Ok(Ok(sender1))) => sender1,
+                _ => AccountId::from([0x0; 32]),
             };
-            let required_gas = user_op
-                .verification_gas_limit
-                .checked_mul(mul)
-                .and_then(|pre| pre.checked_add(user_op.call_gas_limit))
-                .and_then(|pre| pre.checked_add(user_op.pre_verification_gas))
-                .ok_or(Error::GasValuesOverflow)?;
-            let res = required_gas
-                .checked_mul(user_op.max_fee_per_gas)
-                .ok_or(Error::GasValuesOverflow)?;
-            Ok(res)
+            if sender1 == AccountId::from([0x0; 32]) {
+                return Err(Error::FailedOp {
+                    op_index,
+                    reason: FailedOpReason::InitCodeFailed,
+                });
+            }
+            if sender1 != sender {
+                return Err(Error::FailedOp {
+                    op_index,
+                    reason: FailedOpReason::InitCodeMustReturnSender,
+                });
+            }
+            if !self.env().is_contract(&sender1) {
+                return Err(Error::FailedOp {
+                    op_index,
+                    reason: FailedOpReason::InitCodeMustCreateSender,
+                });
+            }
+
+            ink::codegen::EmitEvent::<Self>::emit_event(
+                self.env(),
+                AccountDeployed {
+                    user_op_hash: op_info.user_op_hash.into(),
+                    sender,
+                    factory: m_user_op.factory().unwrap_or(AccountId::from([0x0; 32])),
+                    paymaster: m_user_op.paymaster_and_data.paymaster(),
+                },
+            );
+            Ok(())
         }
 
         /*
@@ -470,10 +686,9 @@ mod entry_point {
             required_prefund: Balance,
         ) -> Result<(u64, ValidationData<AAEnvironment>)> {
             let pre_gas = self.env().gas_left();
-            let m_user_op = &mut out_op_info.user_op;
+            self.create_sender_if_needed(op_index, out_op_info, &user_op.init_code)?;
+            let m_user_op = &out_op_info.user_op;
             let sender = m_user_op.sender;
-            // TODO:
-            // self.create_sender_if_needed(op_index, out_op_info, m_user_op.init_code);
             let paymaster = m_user_op.paymaster_and_data.paymaster();
             let missing_account_funds = if m_user_op.paymaster_and_data.is_eq_zero() {
                 let bal = self.balance_of(sender);
@@ -498,7 +713,7 @@ mod entry_point {
                 Err(e) => {
                     return Err(Error::FailedOp {
                         op_index,
-                        reason: format!("AA23 reverted: {:?}", e),
+                        reason: FailedOpReason::AccountReverted(Box::new(e)),
                     })
                 }
             };
@@ -509,7 +724,7 @@ mod entry_point {
                 if required_prefund > deposit {
                     return Err(Error::FailedOp {
                         op_index,
-                        reason: "AA21 didn't pay prefund".into(),
+                        reason: FailedOpReason::DidNotPayPrefund,
                     });
                 }
                 self.stake_manager
@@ -536,55 +751,49 @@ mod entry_point {
             let ops_len = ops.len();
             let mut op_infos = Vec::with_capacity(ops_len);
             for (i, op) in ops.iter().enumerate() {
-                let mut op_info = UserOpInfo::default();
-                match self.validate_prepayment(i as u64, op, &mut op_info) {
-                    Ok((validation_data, pm_validation_data)) => {
-                        if let Err(e) = self.validate_account_and_paymaster_validation_data(
-                            i as u64,
-                            validation_data,
-                            pm_validation_data,
-                            Aggregator::NoAggregator,
-                        ) {
-                            ink::codegen::EmitEvent::<Self>::emit_event(
-                                self.env(),
-                                UserOperationReturnValue {
-                                    user_op_hash: op.hash().into(),
-                                    success: false,
-                                    result: OpaqueTypes(format!("{e:?}").into_bytes()),
-                                },
-                            );
-                        } else {
-                            op_infos.push((i, op, op_info));
-                        }
-                    }
-                    Err(e) => {
-                        ink::codegen::EmitEvent::<Self>::emit_event(
-                            self.env(),
-                            UserOperationReturnValue {
-                                user_op_hash: op.hash().into(),
-                                success: false,
-                                result: OpaqueTypes(format!("{e:?}").into_bytes()),
-                            },
-                        );
-                    }
+                match self.call_inner_validate_op(i as u64, op, &Aggregator::NoAggregator) {
+                    Ok(op_info) => op_infos.push((i, op, op_info)),
+                    Err(e) => self.emit_op_failure(i as u64, op, e),
                 }
             }
             let mut collected = 0;
             ink::codegen::EmitEvent::<Self>::emit_event(self.env(), BeforeExecution {});
             for (i, op, ref mut op_info) in op_infos {
-                collected += self
-                    .execute_user_op(i as u64, op, op_info)
-                    .unwrap_or_default();
+                match self.execute_user_op(i as u64, op, op_info) {
+                    Ok(actual_gas_cost) => collected += actual_gas_cost,
+                    Err(e) => self.emit_op_failure(i as u64, op, e),
+                }
             }
             self.compensate(beneficiary, collected as Balance)?;
             Ok(())
         }
 
-        fn inner_get_user_op_hash(&self, user_op: &UserOperation<AAEnvironment>) -> [u8; 32] {
+        /**
+         * 报告失败的 UserOperation,不影响同一批次中的其他操作。
+         * 没有携带 `op_index` 的错误包装为 `Error::FailedOp`,事件的 `result` 为其 SCALE 编码。
+         */
+        fn emit_op_failure(&self, op_index: u64, op: &UserOperation<AAEnvironment>, error: Error) {
             use scale::Encode;
-            ink_aa::core::helpers::keccak256(
-                &(user_op.hash(), self.env().account_id().encode()).encode(),
-            )
+            let error = match error {
+                Error::FailedOp { .. } => error,
+                e => Error::FailedOp {
+                    op_index,
+                    reason: FailedOpReason::Other(Box::new(e)),
+                },
+            };
+            ink::codegen::EmitEvent::<Self>::emit_event(
+                self.env(),
+                UserOperationReturnValue {
+                    user_op_hash: self.inner_get_user_op_hash(op).into(),
+                    call_index: 0,
+                    success: false,
+                    result: OpaqueTypes(error.encode()),
+                },
+            );
+        }
+
+        fn inner_get_user_op_hash(&self, user_op: &UserOperation<AAEnvironment>) -> [u8; 32] {
+            user_op.user_op_hash(&self.env().account_id(), &self.chain_id)
         }
 
         /**
@@ -606,7 +815,10 @@ mod entry_point {
             let m_user_op = &op_info.user_op;
             let verification_gas_limit = m_user_op.verification_gas_limit;
             if verification_gas_limit <= gas_used_by_validate_account_prepayment {
-                return Err(Error::TooLittleVerificationGas);
+                return Err(Error::FailedOp {
+                    op_index,
+                    reason: FailedOpReason::TooLittleVerificationGas,
+                });
             }
             // TODO:
             // let gas = verification_gas_limit
@@ -616,7 +828,10 @@ mod entry_point {
             let paymaster_info = self.get_deposit_info(paymaster);
             let deposit = paymaster_info.deposit;
             if deposit < required_pre_fund {
-                return Err(Error::PaymasterDepositTooLow);
+                return Err(Error::FailedOp {
+                    op_index,
+                    reason: FailedOpReason::PaymasterDepositTooLow,
+                });
             }
 
             self.stake_manager
@@ -632,7 +847,7 @@ mod entry_point {
                 )
                 .map_err(|e| Error::FailedOp {
                     op_index,
-                    reason: format!("AA33 reverted: {e:?}"),
+                    reason: FailedOpReason::PaymasterReverted(Box::new(e)),
                 })?;
             Ok((context, validation_data))
         }
@@ -651,13 +866,13 @@ mod entry_point {
             if expected_aggregator != aggregator {
                 return Err(Error::FailedOp {
                     op_index,
-                    reason: "AA24 signature error".into(),
+                    reason: FailedOpReason::SignatureError,
                 });
             }
             if out_of_time_range {
                 return Err(Error::FailedOp {
                     op_index,
-                    reason: "AA22 expired or not due".into(),
+                    reason: FailedOpReason::ExpiredOrNotDue,
                 });
             }
             //pmAggregator is not a real signature aggregator: we don't have logic to handle it as address.
@@ -667,18 +882,26 @@ mod entry_point {
             if pm_aggregator != Aggregator::NoAggregator {
                 return Err(Error::FailedOp {
                     op_index,
-                    reason: "AA34 signature error".into(),
+                    reason: FailedOpReason::PaymasterSignatureError,
                 });
             }
             if out_of_time_range {
                 return Err(Error::FailedOp {
                     op_index,
-                    reason: "AA32 paymaster expired or not due".into(),
+                    reason: FailedOpReason::PaymasterExpiredOrNotDue,
                 });
             }
             Ok(())
         }
 
+        fn get_stake_info(&self, addr: AccountId) -> StakeInfo<AAEnvironment> {
+            let info = self.stake_manager.get_deposit_info(addr);
+            StakeInfo {
+                stake: info.stake,
+                unstake_delay_sec: info.unstake_delay_sec,
+            }
+        }
+
         fn get_validation_data(
             &self,
             validation_data: ValidationData<AAEnvironment>,
@@ -699,12 +922,7 @@ mod entry_point {
             beneficiary: AccountId,
         ) -> Result<()> {
             // 校验,执行每个aggregator下的user ops
-            let total_ops = ops_per_aggregator
-                .iter()
-                .map(|opa| opa.user_ops.len())
-                .sum();
-
-            let mut op_infos = Vec::with_capacity(total_ops);
+            let mut groups = Vec::with_capacity(ops_per_aggregator.len());
 
             let mut op_index = 0;
             for opa in ops_per_aggregator {
@@ -712,39 +930,55 @@ mod entry_point {
                     return Err(Error::InvalidAggregator);
                 }
 
+                let aggregator_address = match opa.aggregator {
+                    Aggregator::VerifiedBy(address) => address,
+                    _ => AccountId::from([0x0; 32]),
+                };
                 if let Aggregator::VerifiedBy(address) = opa.aggregator {
                     let aggregator: AggregatorRef<AAEnvironment> = address.into();
                     ink_aa::traits::aggregator::IAggregator::validate_signatures(
                         &aggregator,
                         opa.user_ops.clone(),
                         opa.signature.clone(),
-                    )?;
+                    )
+                    .map_err(|_| Error::SignatureValidationFailed {
+                        aggregator: address,
+                    })?;
                 }
 
+                let mut op_infos = Vec::with_capacity(opa.user_ops.len());
                 for op in opa.user_ops {
-                    let mut op_info = UserOpInfo::default();
-
-                    let (validation_data, pm_validation_data) =
-                        self.validate_prepayment(op_index, &op, &mut op_info)?;
-
-                    self.validate_account_and_paymaster_validation_data(
-                        op_index,
-                        validation_data,
-                        pm_validation_data,
-                        opa.aggregator.clone(),
-                    )?;
-
-                    op_infos.push((op_index, op, op_info));
+                    match self.call_inner_validate_op(op_index, &op, &opa.aggregator) {
+                        Ok(op_info) => op_infos.push((op_index, op, op_info)),
+                        Err(e) => self.emit_op_failure(op_index, &op, e),
+                    }
                     op_index += 1;
                 }
+                groups.push((aggregator_address, op_infos));
             }
 
             // 执行
             let mut collected = 0;
+            ink::codegen::EmitEvent::<Self>::emit_event(self.env(), BeforeExecution {});
 
-            for (i, op, ref mut op_info) in op_infos {
-                collected += self.execute_user_op(i, &op, op_info)?;
+            for (aggregator, op_infos) in groups {
+                ink::codegen::EmitEvent::<Self>::emit_event(
+                    self.env(),
+                    SignatureAggregatorChanged { aggregator },
+                );
+                for (i, op, ref mut op_info) in op_infos {
+                    match self.execute_user_op(i, &op, op_info) {
+                        Ok(actual_gas_cost) => collected += actual_gas_cost,
+                        Err(e) => self.emit_op_failure(i, &op, e),
+                    }
+                }
             }
+            ink::codegen::EmitEvent::<Self>::emit_event(
+                self.env(),
+                SignatureAggregatorChanged {
+                    aggregator: AccountId::from([0x0; 32]),
+                },
+            );
 
             self.compensate(beneficiary, collected as Balance)?;
 
@@ -771,6 +1005,116 @@ mod entry_point {
             Ok(())
         }
         #[ink(message)]
+        fn simulate_validation(&mut self, user_op: UserOperation<AAEnvironment>) -> Result<()> {
+            let mut op_info = UserOpInfo::default();
+            let (validation_data, paymaster_validation_data) =
+                self.validate_prepayment(0, &user_op, &mut op_info)?;
+            self.validate_account_and_paymaster_validation_data(
+                0,
+                validation_data.clone(),
+                paymaster_validation_data.clone(),
+                validation_data.aggregator.clone(),
+            )?;
+
+            let sender_info = self.get_stake_info(user_op.sender);
+            let factory_info =
+                self.get_stake_info(user_op.factory().unwrap_or(AccountId::from([0x0; 32])));
+            let paymaster_info = self.get_stake_info(user_op.paymaster_and_data.paymaster());
+
+            let data = intersect_time_range(validation_data, paymaster_validation_data);
+            let return_info = ReturnInfo {
+                pre_op_gas: op_info.pre_op_gas,
+                prefund: op_info.prefund,
+                sig_failed: data.aggregator == Aggregator::IllegalAggregator,
+                valid_after: data.valid_after,
+                valid_until: data.valid_until,
+                paymaster_context: op_info.context,
+            };
+
+            if let Aggregator::VerifiedBy(aggregator) = data.aggregator {
+                return Err(Error::ValidationResultWithAggregation {
+                    return_info,
+                    sender_info,
+                    factory_info,
+                    paymaster_info,
+                    aggregator_info: AggregatorStakeInfo {
+                        aggregator,
+                        stake_info: self.get_stake_info(aggregator),
+                    },
+                });
+            }
+
+            Err(Error::ValidationResult {
+                return_info,
+                sender_info,
+                factory_info,
+                paymaster_info,
+            })
+        }
+        #[ink(message)]
+        fn simulate_handle_op(
+            &mut self,
+            op: UserOperation<AAEnvironment>,
+            target: AccountId,
+            target_call_data: Vec<u8>,
+        ) -> Result<()> {
+            let mut op_info = UserOpInfo::default();
+            let (validation_data, paymaster_validation_data) =
+                self.validate_prepayment(0, &op, &mut op_info)?;
+            let data = intersect_time_range(validation_data, paymaster_validation_data);
+
+            self.execute_user_op(0, &op, &op_info)?;
+
+            let (target_success, target_result) =
+                if target != AccountId::from([0x0; 32]) && target_call_data.len() >= 4 {
+                    let mut selector = [0u8; 4];
+                    selector.copy_from_slice(&target_call_data[..4]);
+                    let call = Transaction::<AAEnvironment>::new(
+                        target,
+                        selector,
+                        target_call_data[4..].to_vec(),
+                        0,
+                        self.env().gas_left(),
+                    )
+                    .call();
+                    match call.try_invoke() {
+                        Ok(Ok(result)) => (true, result.0),
+                        e => (false, format!("{:?}", e).into_bytes()),
+                    }
+                } else {
+                    (false, Vec::new())
+                };
+
+            Err(Error::ExecutionResult {
+                pre_op_gas: op_info.pre_op_gas,
+                prefund: op_info.prefund,
+                valid_after: data.valid_after,
+                valid_until: data.valid_until,
+                target_success,
+                target_result,
+            })
+        }
+        #[ink(message)]
+        fn get_sender_address(&mut self, init_code: Vec<u8>) -> Result<()> {
+            use ink::codegen::TraitCallBuilder;
+            let sender = match self
+                .sender_creator
+                .call_mut()
+                .create_sender(init_code)
+                .try_invoke()
+            {
+                Ok(Ok(Ok(sender))) => sender,
+                _ => AccountId::from([0x0; 32]),
+            };
+            if sender == AccountId::from([0x0; 32]) {
+                return Err(Error::FailedOp {
+                    op_index: 0,
+                    reason: FailedOpReason::InitCodeFailed,
+                });
+            }
+            Err(Error::SenderAddressResult { sender })
+        }
+        #[ink(message)]
         fn get_user_op_hash(&self, user_op: UserOperation<AAEnvironment>) -> [u8; 32] {
             self.inner_get_user_op_hash(&user_op)
         }
@@ -785,21 +1129,33 @@ mod entry_point {
         fn balance_of(&self, account: AccountId) -> Balance {
             self.stake_manager.balance_of(account)
         }
+        /// 转入的金额由 EntryPoint 持有,用于支付 gas 费用,押金记录在 StakeManager 中。
         #[ink(message, payable)]
         fn deposit_to(&mut self, account: AccountId) -> Result<()> {
-            self.stake_manager.deposit_to(account)
+            self.stake_manager
+                .deposit_for(account, self.env().transferred_value())
         }
+        /// 抵押同样由 EntryPoint 持有。
         #[ink(message, payable)]
         fn add_stake(&mut self, unstake_delay_sec: Timestamp) -> Result<()> {
-            self.stake_manager.add_stake(unstake_delay_sec)
+            self.stake_manager.add_stake_for(
+                self.env().caller(),
+                unstake_delay_sec,
+                self.env().transferred_value(),
+            )
         }
         #[ink(message)]
         fn unlock_stake(&mut self) -> Result<()> {
-            self.stake_manager.unlock_stake()
+            self.stake_manager.unlock_stake_for(self.env().caller())
         }
         #[ink(message, payable)]
         fn withdraw_stake(&mut self, withdraw_address: AccountId) -> Result<()> {
-            self.stake_manager.withdraw_stake(withdraw_address)
+            let stake = self
+                .stake_manager
+                .withdraw_stake_for(self.env().caller(), withdraw_address)?;
+            self.env()
+                .transfer(withdraw_address, stake)
+                .map_err(|_| Error::FailedToWithdrawStake)
         }
         #[ink(message, payable)]
         fn withdraw_to(
@@ -807,8 +1163,14 @@ mod entry_point {
             withdraw_address: AccountId,
             withdraw_amount: Balance,
         ) -> Result<()> {
-            self.stake_manager
-                .withdraw_to(withdraw_address, withdraw_amount)
+            self.stake_manager.withdraw_for(
+                self.env().caller(),
+                withdraw_address,
+                withdraw_amount,
+            )?;
+            self.env()
+                .transfer(withdraw_address, withdraw_amount)
+                .map_err(|_| Error::FailedToWithdraw)
         }
     }
 
@@ -819,8 +1181,71 @@ mod entry_point {
         }
 
         #[ink(message)]
-        fn increment_nonce(&mut self, key: [u8; 24]) {
-            self.nonce_manager.increment_nonce(key)
+        fn increment_nonce(&mut self, key: [u8; 24]) -> Result<()> {
+            self.nonce_manager
+                .increment_nonce_of(self.env().caller(), key)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::call::FromAccountId;
+
+        fn entry_point() -> EntryPoint {
+            EntryPoint {
+                stake_manager: FromAccountId::from_account_id(AccountId::from([1; 32])),
+                nonce_manager: FromAccountId::from_account_id(AccountId::from([2; 32])),
+                sender_creator: FromAccountId::from_account_id(AccountId::from([3; 32])),
+                chain_id: Hash::from([4; 32]),
+            }
+        }
+
+        #[ink::test]
+        fn inner_validate_op_is_internal() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            ink::env::test::set_caller::<AAEnvironment>(accounts.bob);
+            let mut entry_point = entry_point();
+
+            assert!(matches!(
+                entry_point.inner_validate_op(
+                    0,
+                    UserOperation::default(),
+                    Aggregator::NoAggregator
+                ),
+                Err(Error::OnlyInternalCall)
+            ));
+        }
+
+        #[ink::test]
+        fn inner_handle_op_is_internal() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            ink::env::test::set_caller::<AAEnvironment>(accounts.bob);
+            let mut entry_point = entry_point();
+
+            assert_eq!(
+                entry_point.inner_handle_op(
+                    UserOperationCall::Batch(Vec::new()),
+                    UserOpInfo::default(),
+                    Vec::new()
+                ),
+                Err(Error::OnlyInternalCall)
+            );
+        }
+
+        #[ink::test]
+        fn illegal_aggregator_rejects_bundle() {
+            let mut entry_point = entry_point();
+            let ops_per_aggregator = vec![UserOpsPerAggregator {
+                user_ops: vec![UserOperation::default()],
+                aggregator: Aggregator::IllegalAggregator,
+                signature: Vec::new(),
+            }];
+
+            assert_eq!(
+                entry_point.handle_aggregated_ops(ops_per_aggregator, AccountId::from([5; 32])),
+                Err(Error::InvalidAggregator)
+            );
         }
     }
 
@@ -834,105 +1259,464 @@ mod entry_point {
         /// Imports all the definitions from the outer scope so we can use them here.
         use super::*;
 
+        use account_factory::AccountFactoryRef;
         use base_account::BaseAccountRef;
+        use base_paymaster::BasePaymasterRef;
         use flip::FlipRef;
+        use ink_aa::core::{signature::MultiSignature, user_operation::PaymasterAndData};
         /// A helper function used for calling contract messages.
-        use ink_e2e::build_message;
+        use ink_e2e::{build_message, AccountKeyring};
         use recover_sig::RecoverSigRef;
+        use scale::Encode;
         use simple_paymaster::SimplePaymasterRef;
+        use sr25519_aggregator::Sr25519AggregatorRef;
+        use test_paymaster::TestPaymasterRef;
 
         /// The End-to-End test `Result` type.
         type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-        /// We test that we can upload and instantiate the contract using its default constructor.
-        #[ink_e2e::test]
-        async fn default_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
-            let sig = RecoverSigRef::new(
-                2,
-                vec![ink_e2e::bob().account_id(), ink_e2e::eve().account_id()],
-            );
-            let sig_id = client
-                .instantiate("recover_sig", &ink_e2e::bob(), 1000, None)
-                .await
-                .expect("uploading `recover_sig` failed")
-                .code_hash;
+        type Client = ink_e2e::Client<ink_e2e::PolkadotConfig, AAEnvironment>;
 
-            let constructor = FlipRef::new(false);
-            let contract_account_id = client
-                .instantiate("flip", &ink_e2e::bob(), constructor, 0, None)
-                .await
-                .expect("instantiate failed")
-                .account_id;
+        const CHAIN_ID: [u8; 32] = [0x01; 32];
 
-            let get = build_message::<FlipRef>(contract_account_id.clone()).call(|flip| flip.get());
-            let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
-            assert!(matches!(get_result.return_value(), false));
+        /// 直接提交 `handle_ops` 时使用的 gas 上限。
+        const GAS_LIMIT: u128 = 1_000_000_000_000;
+        const PROOF_SIZE_LIMIT: u128 = 3 * 1024 * 1024;
+
+        /// 测试中部署的合约。
+        struct Deployment {
+            entry_point: AccountId,
+            /// 由 bob 单独签名的 BaseAccount。
+            account: AccountId,
+            /// 使用 SimplePaymaster 的 BasePaymaster。
+            paymaster: AccountId,
+        }
 
+        async fn deploy(client: &mut Client) -> Deployment {
+            let alice = ink_e2e::alice();
             let stake_manager_hash = client
-                .upload("stake_manager", &ink_e2e::alice(), None)
+                .upload("stake_manager", &alice, None)
                 .await
                 .expect("uploading `stake_manager` failed")
                 .code_hash;
             let nonce_manager_hash = client
-                .upload("nonce_manager", &ink_e2e::alice(), None)
+                .upload("nonce_manager", &alice, None)
                 .await
                 .expect("uploading `nonce_manager` failed")
                 .code_hash;
+            let sender_creator_hash = client
+                .upload("sender_creator", &alice, None)
+                .await
+                .expect("uploading `sender_creator` failed")
+                .code_hash;
             let constructor = EntryPointRef::new(
                 1337, // salt
                 stake_manager_hash,
                 nonce_manager_hash,
+                sender_creator_hash,
+                Hash::from(CHAIN_ID),
             );
+            let entry_point = client
+                .instantiate("entry_point", &alice, constructor, 0, None)
+                .await
+                .expect("instantiate `entry_point` failed")
+                .account_id;
 
-            let entry_point_acc_id = client
-                .instantiate("entry_point", &ink_e2e::alice(), constructor, 10000, None)
+            let owner = ink_e2e::account_id(AccountKeyring::Bob);
+            let validator = client
+                .instantiate(
+                    "recover_sig",
+                    &alice,
+                    RecoverSigRef::new(1, vec![owner]),
+                    0,
+                    None,
+                )
                 .await
-                .expect("instantiate failed")
+                .expect("instantiate `recover_sig` failed")
                 .account_id;
+            let account = client
+                .instantiate(
+                    "base_account",
+                    &alice,
+                    BaseAccountRef::new(entry_point, validator),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate `base_account` failed")
+                .account_id;
+            let set_account = build_message::<RecoverSigRef>(validator)
+                .call(|validator| validator.set_account(account));
+            client
+                .call(&alice, set_account, 0, None)
+                .await
+                .expect("set_account failed");
+
+            let simple_paymaster = client
+                .instantiate(
+                    "simple_paymaster",
+                    &alice,
+                    SimplePaymasterRef::new(),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate `simple_paymaster` failed")
+                .account_id;
+            let paymaster = base_paymaster(client, entry_point, simple_paymaster).await;
+
+            Deployment {
+                entry_point,
+                account,
+                paymaster,
+            }
+        }
+
+        /// 部署将验证和 postOp 转发给 `advance` 的 BasePaymaster。
+        async fn base_paymaster(
+            client: &mut Client,
+            entry_point: AccountId,
+            advance: AccountId,
+        ) -> AccountId {
+            let constructor = BasePaymasterRef::new(
+                entry_point,
+                ink_e2e::account_id(AccountKeyring::Alice),
+                advance,
+            );
+            client
+                .instantiate("base_paymaster", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate `base_paymaster` failed")
+                .account_id
+        }
+
+        async fn deposit_to(client: &mut Client, entry_point: AccountId, account: AccountId) {
+            let deposit_to = build_message::<EntryPointRef>(entry_point)
+                .call(|entry_point| entry_point.deposit_to(account));
+            client
+                .call(&ink_e2e::alice(), deposit_to, 1_000_000_000_000, None)
+                .await
+                .expect("deposit_to failed");
+        }
+
+        async fn balance_of(
+            client: &mut Client,
+            entry_point: AccountId,
+            account: AccountId,
+        ) -> Balance {
+            let balance_of = build_message::<EntryPointRef>(entry_point)
+                .call(|entry_point| entry_point.balance_of(account));
+            client
+                .call_dry_run(&ink_e2e::alice(), &balance_of, 0, None)
+                .await
+                .return_value()
+        }
+
+        async fn nonce(client: &mut Client, entry_point: AccountId, sender: AccountId) -> [u8; 32] {
+            let get_nonce = build_message::<EntryPointRef>(entry_point)
+                .call(|entry_point| entry_point.get_nonce(sender, [0; 24]));
+            client
+                .call_dry_run(&ink_e2e::alice(), &get_nonce, 0, None)
+                .await
+                .return_value()
+        }
+
+        /// 以 `GAS_LIMIT` 调用 `contract`。`inner_handle_op` 在执行前检查剩余的 gas
+        /// 是否足够用户操作使用,而 `Client::call` 只按 dry-run 实际消耗的 gas 提交。
+        async fn call_with_gas_limit(client: &mut Client, contract: AccountId, input: Vec<u8>) {
+            use ink_e2e::subxt::dynamic::Value;
+            let call_data = vec![
+                Value::unnamed_variant("Id", [Value::from_bytes(contract)]),
+                Value::u128(0),
+                Value::named_composite([
+                    ("ref_time", Value::u128(GAS_LIMIT)),
+                    ("proof_size", Value::u128(PROOF_SIZE_LIMIT)),
+                ]),
+                Value::unnamed_variant("None", []),
+                Value::from_bytes(input),
+            ];
+            client
+                .runtime_call(&ink_e2e::alice(), "Contracts", "call", call_data)
+                .await
+                .expect("call failed");
+        }
+
+        /// 由账户的所有者 bob 签名。
+        async fn sign(
+            client: &mut Client,
+            entry_point: AccountId,
+            mut op: UserOperation<AAEnvironment>,
+        ) -> UserOperation<AAEnvironment> {
+            let get_user_op_hash = build_message::<EntryPointRef>(entry_point)
+                .call(|entry_point| entry_point.get_user_op_hash(op.clone()));
+            let user_op_hash = client
+                .call_dry_run(&ink_e2e::alice(), &get_user_op_hash, 0, None)
+                .await
+                .return_value();
+            let signature = ink_e2e::bob().sign(&user_op_hash);
+            op.signature = vec![(
+                ink_e2e::account_id(AccountKeyring::Bob),
+                MultiSignature::Sr25519(signature.0),
+            )]
+            .encode();
+            op
+        }
 
-            let base = BaseAccountRef::new(entry_point_acc_id.clone(), sig_id.clone());
-            let base_id = client
-                .instantiate("base_account", &ink_e2e::bob(), base, 1000, None)
+        /// 没有签名、不执行调用的用户操作。
+        fn user_op(
+            sender: AccountId,
+            nonce: [u8; 32],
+            paymaster: AccountId,
+        ) -> UserOperation<AAEnvironment> {
+            UserOperation {
+                sender,
+                nonce,
+                call_gas_limit: 100_000_000_000,
+                verification_gas_limit: 200_000_000_000,
+                max_fee_per_gas: 1,
+                max_priority_fee_per_gas: 1,
+                paymaster_and_data: PaymasterAndData::OnlyPaymaster(paymaster),
+                ..Default::default()
+            }
+        }
+
+        /// 工厂预先计算的地址与 EntryPoint 通过 `init_code` 创建的账户地址一致。
+        #[ink_e2e::test]
+        async fn factory_address_matches_sender_address(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let alice = ink_e2e::alice();
+            let Deployment { entry_point, .. } = deploy(&mut client).await;
+            let account_hash = client
+                .upload("base_account", &alice, None)
                 .await
                 .expect("uploading `base_account` failed")
                 .code_hash;
+            let validator_hash = client
+                .upload("recover_sig", &alice, None)
+                .await
+                .expect("uploading `recover_sig` failed")
+                .code_hash;
+            let factory = client
+                .instantiate(
+                    "account_factory",
+                    &alice,
+                    AccountFactoryRef::new(entry_point, account_hash, validator_hash),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate `account_factory` failed")
+                .account_id;
+            let owners = vec![ink_e2e::account_id(AccountKeyring::Bob)];
+            let salt = [7; 32];
 
-            // When
-            let flip =
-                build_message::<FlipRef>(contract_account_id.clone()).call(|flip| flip.flip());
-            // let _flip_result = client
-            //     .call(&ink_e2e::bob(), flip, 0, None)
-            //     .await
-            //     .expect("flip failed");
-            let op = UserOperation {
-                sender: base_id,
-                nonce: [0; 32],
-                init_code: vec![],
-                callee: contract_account_id.clone(),
-                selector: [99, 58, 165, 81],
-                call_data: vec![],
-                call_gas_limit: 9798418432,
-                verification_gas_limit: 9798418432,
-                pre_verification_gas: 9798418432,
-                max_fee_per_gas: 19798418432,
-                max_priority_fee_per_gas: 9798418432,
-                paymaster: AccountId::from([0; 32]),
-                paymaster_data: vec![],
-                signature: vec![],
-            };
+            let get_address = build_message::<AccountFactoryRef>(factory)
+                .call(|factory| factory.get_address(1, owners.clone(), salt));
+            let address = client
+                .call_dry_run(&alice, &get_address, 0, None)
+                .await
+                .return_value();
 
-            let handle_ops = build_message::<EntryPointRef>(multi_contract_caller_acc_id.clone())
-                .call(|contract| contract.handle_ops(vec![op], ink_e2e::alice().account_id()));
-            let res = client
-                .call(&ink_e2e::alice(), handle_ops, 2000, None)
+            let mut init_code = factory.encode();
+            init_code.extend(ink::selector_bytes!("create_account"));
+            (1u32, owners, salt).encode_to(&mut init_code);
+            let get_sender_address = build_message::<EntryPointRef>(entry_point)
+                .call(|entry_point| entry_point.get_sender_address(init_code.clone()));
+            let result = client
+                .call_dry_run(&alice, &get_sender_address, 0, None)
                 .await
                 .return_value();
-            println!("{:?}", res);
+            assert_eq!(result, Err(Error::SenderAddressResult { sender: address }));
+            Ok(())
+        }
+
+        /// 存款由 EntryPoint 持有,取款时从 EntryPoint 的余额中转出。
+        #[ink_e2e::test]
+        async fn deposit_can_be_withdrawn(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let Deployment { entry_point, .. } = deploy(&mut client).await;
+            let alice = ink_e2e::account_id(AccountKeyring::Alice);
+            let dave = ink_e2e::account_id(AccountKeyring::Dave);
+            deposit_to(&mut client, entry_point, alice).await;
+            let deposit = balance_of(&mut client, entry_point, alice).await;
+            assert_eq!(deposit, 1_000_000_000_000);
+            let dave_balance = client.balance(dave).await.expect("balance failed");
+
+            let withdraw_to = build_message::<EntryPointRef>(entry_point)
+                .call(|entry_point| entry_point.withdraw_to(dave, deposit));
+            client
+                .call(&ink_e2e::alice(), withdraw_to, 0, None)
+                .await
+                .expect("withdraw_to failed");
+
+            assert_eq!(balance_of(&mut client, entry_point, alice).await, 0);
+            assert_eq!(
+                client.balance(dave).await.expect("balance failed"),
+                dave_balance + deposit
+            );
+            Ok(())
+        }
+
+        /// 验证在扣除押金、更新 nonce 之后失败的用户操作不会保留这些变化。
+        #[ink_e2e::test]
+        async fn failed_validation_is_rolled_back(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let Deployment {
+                entry_point,
+                account,
+                paymaster,
+            } = deploy(&mut client).await;
+            deposit_to(&mut client, entry_point, account).await;
+            deposit_to(&mut client, entry_point, paymaster).await;
+            let account_deposit = balance_of(&mut client, entry_point, account).await;
+            let paymaster_deposit = balance_of(&mut client, entry_point, paymaster).await;
+            let nonce_before = nonce(&mut client, entry_point, account).await;
+
+            // 扣除 paymaster 的押金、更新 nonce 后因为没有签名而失败。
+            let sponsored = user_op(account, nonce_before, paymaster);
+            // 扣除账户的押金后因为 nonce 错误而失败。
+            let mut wrong_nonce = nonce_before;
+            wrong_nonce[31] = wrong_nonce[31].wrapping_add(1);
+            let unsponsored = sign(
+                &mut client,
+                entry_point,
+                user_op(account, wrong_nonce, AccountId::from([0; 32])),
+            )
+            .await;
+            let handle_ops = build_message::<EntryPointRef>(entry_point).call(|entry_point| {
+                entry_point.handle_ops(
+                    vec![sponsored.clone(), unsponsored.clone()],
+                    ink_e2e::account_id(AccountKeyring::Alice),
+                )
+            });
+            client
+                .call(&ink_e2e::alice(), handle_ops, 0, None)
+                .await
+                .expect("handle_ops failed");
+
+            let handle_aggregated_ops =
+                build_message::<EntryPointRef>(entry_point).call(|entry_point| {
+                    entry_point.handle_aggregated_ops(
+                        vec![UserOpsPerAggregator {
+                            user_ops: vec![sponsored.clone(), unsponsored.clone()],
+                            aggregator: Aggregator::NoAggregator,
+                            signature: Vec::new(),
+                        }],
+                        ink_e2e::account_id(AccountKeyring::Alice),
+                    )
+                });
+            client
+                .call(&ink_e2e::alice(), handle_aggregated_ops, 0, None)
+                .await
+                .expect("handle_aggregated_ops failed");
+
+            assert_eq!(
+                balance_of(&mut client, entry_point, account).await,
+                account_deposit
+            );
+            assert_eq!(
+                balance_of(&mut client, entry_point, paymaster).await,
+                paymaster_deposit
+            );
+            assert_eq!(nonce(&mut client, entry_point, account).await, nonce_before);
+            Ok(())
+        }
 
-            // Then
-            let get = build_message::<FlipRef>(contract_account_id.clone()).call(|flip| flip.get());
-            let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
-            assert!(matches!(get_result.return_value(), true));
+        /// postOp 失败时 inner_handle_op 的调用帧被回滚,用户的调用随之撤销,
+        /// EntryPoint 再以 PostOpReverted 调用 postOp,向 paymaster 收取实际的费用。
+        #[ink_e2e::test]
+        async fn post_op_reverted_is_retried(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let alice = ink_e2e::alice();
+            let Deployment {
+                entry_point,
+                account,
+                ..
+            } = deploy(&mut client).await;
+            let test_paymaster = client
+                .instantiate("test_paymaster", &alice, TestPaymasterRef::new(), 0, None)
+                .await
+                .expect("instantiate `test_paymaster` failed")
+                .account_id;
+            let paymaster = base_paymaster(&mut client, entry_point, test_paymaster).await;
+            deposit_to(&mut client, entry_point, paymaster).await;
+            let flip = client
+                .instantiate("flip", &alice, FlipRef::new(false), 0, None)
+                .await
+                .expect("instantiate `flip` failed")
+                .account_id;
+            let paymaster_deposit = balance_of(&mut client, entry_point, paymaster).await;
+            let nonce_before = nonce(&mut client, entry_point, account).await;
+
+            let op = UserOperation {
+                call: UserOperationCall::Call {
+                    callee: flip,
+                    selector: ink::selector_bytes!("flip"),
+                    call_data: Vec::new(),
+                    value: 0,
+                },
+                ..user_op(account, nonce_before, paymaster)
+            };
+            let op = sign(&mut client, entry_point, op).await;
+            let prefund = op.required_prefund().expect("prefund overflow") as Balance;
+            let handle_ops = build_message::<EntryPointRef>(entry_point).call(|entry_point| {
+                entry_point.handle_ops(vec![op.clone()], ink_e2e::account_id(AccountKeyring::Alice))
+            });
+            call_with_gas_limit(&mut client, entry_point, handle_ops.exec_input().to_vec()).await;
+
+            let get = build_message::<FlipRef>(flip).call(|flip| flip.get());
+            assert!(!client
+                .call_dry_run(&alice, &get, 0, None)
+                .await
+                .return_value());
+            assert_ne!(nonce(&mut client, entry_point, account).await, nonce_before);
+            // 第二次 postOp 成功,未使用的预付款退还给 paymaster。
+            let charged = paymaster_deposit - balance_of(&mut client, entry_point, paymaster).await;
+            assert!(0 < charged && charged < prefund);
+            Ok(())
+        }
+
+        /// 聚合器拒绝聚合签名时整个批次回滚。
+        #[ink_e2e::test]
+        async fn rejected_aggregated_signature_reverts_bundle(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let Deployment {
+                entry_point,
+                account,
+                paymaster,
+            } = deploy(&mut client).await;
+            let aggregator = client
+                .instantiate(
+                    "sr25519_aggregator",
+                    &ink_e2e::alice(),
+                    Sr25519AggregatorRef::new(entry_point, Hash::from(CHAIN_ID)),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate `sr25519_aggregator` failed")
+                .account_id;
+            let nonce_before = nonce(&mut client, entry_point, account).await;
+
+            let handle_aggregated_ops =
+                build_message::<EntryPointRef>(entry_point).call(|entry_point| {
+                    entry_point.handle_aggregated_ops(
+                        vec![UserOpsPerAggregator {
+                            user_ops: vec![user_op(account, nonce_before, paymaster)],
+                            aggregator: Aggregator::VerifiedBy(aggregator),
+                            signature: vec![1, 2, 3],
+                        }],
+                        ink_e2e::account_id(AccountKeyring::Alice),
+                    )
+                });
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &handle_aggregated_ops, 0, None)
+                .await;
+            assert_eq!(
+                result.return_value(),
+                Err(Error::SignatureValidationFailed { aggregator })
+            );
             Ok(())
         }
     }
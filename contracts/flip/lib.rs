@@ -1,6 +1,6 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-pub use flip::FlipRef;
+pub use self::flip::FlipRef;
 
 #[ink::contract]
 mod flip {
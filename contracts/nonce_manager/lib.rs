@@ -1,81 +1,274 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-pub use nonce_manager::NonceManagerRef;
+pub use self::nonce_manager::NonceManagerRef;
 
 #[ink::contract(env = ink_aa::core::env::AAEnvironment)]
 mod nonce_manager {
     use ink::storage::Mapping;
-    use ink_aa::traits::nonce_manager::INonceManager;
+    use ink_aa::{
+        core::error::{Error, Result},
+        traits::nonce_manager::INonceManager,
+    };
 
-    /// Defines the storage of your contract.
-    /// Add new fields to the below struct in order
-    /// to add new static storage fields to your contract.
+    /// 按照 ERC-4337 的二维 nonce 管理账户的 nonce。
+    ///
+    /// 完整的 nonce 为 `[u8; 32]`,高 192 位(前 24 字节)为 key,
+    /// 低 64 位(后 8 字节,大端序)为该 key 下的序号。
     #[ink(storage)]
     pub struct NonceManager {
-        /// Stores a single `bool` value on the storage.
-        nonce_sequence_number: Mapping<(AccountId, [u8; 24]), [u8; 32]>,
+        /// 每个 (sender, key) 下一个可用的序号。
+        nonce_sequence_number: Mapping<(AccountId, [u8; 24]), u64>,
+        /// 实例化此合约的 EntryPoint,只有它可以校验并递增其他账户的 nonce。
+        owner: AccountId,
     }
 
     impl NonceManager {
-        /// Constructor that initializes the `bool` value to the given `init_value`.
+        /// 由 EntryPoint 实例化,调用者即为所有者。
         #[ink(constructor)]
         pub fn new() -> Self {
             Self {
                 nonce_sequence_number: Mapping::default(),
+                owner: Self::env().caller(),
             }
         }
 
         #[ink(message)]
-        pub fn validate_and_update_nonce(&mut self, sender: AccountId, nonce: [u8; 32]) -> bool {
-            let mut key = [0; 24];
-            key.copy_from_slice(&nonce[..24]);
-            let seq = nonce;
-            let mut nonce = self.nonce_sequence_number.get((sender, key)).unwrap_or({
-                let mut h = [0; 32];
-                h[..24].copy_from_slice(&key);
-                h
-            });
-
-            if nonce == seq {
-                increment_bytes(&mut nonce);
-                self.nonce_sequence_number
-                    .insert((self.env().caller(), key), &nonce);
-                true
-            } else {
-                false
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// 校验 `nonce` 是否为 `sender` 的下一个 nonce,如果是则递增。
+        /// 只能由所有者调用。
+        #[ink(message)]
+        pub fn validate_and_update_nonce(
+            &mut self,
+            sender: AccountId,
+            nonce: [u8; 32],
+        ) -> Result<()> {
+            self.inner_only_owner()?;
+            let (key, seq) = split_nonce(nonce);
+            let current = self
+                .nonce_sequence_number
+                .get((sender, key))
+                .unwrap_or_default();
+            if current != seq {
+                return Err(Error::InvalidAccountNonce);
+            }
+            let next = seq.checked_add(1).ok_or(Error::InvalidAccountNonce)?;
+            self.nonce_sequence_number.insert((sender, key), &next);
+            Ok(())
+        }
+
+        /// 代替 `sender` 手动递增 nonce,只能由所有者调用。
+        /// EntryPoint 通过它转发账户的 `increment_nonce` 调用。
+        #[ink(message)]
+        pub fn increment_nonce_of(&mut self, sender: AccountId, key: [u8; 24]) -> Result<()> {
+            self.inner_only_owner()?;
+            self.inner_increment_nonce(sender, key);
+            Ok(())
+        }
+
+        fn inner_only_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotFromOwner);
             }
+            Ok(())
+        }
+
+        fn inner_increment_nonce(&mut self, sender: AccountId, key: [u8; 24]) {
+            let seq = self
+                .nonce_sequence_number
+                .get((sender, key))
+                .unwrap_or_default();
+            self.nonce_sequence_number
+                .insert((sender, key), &seq.saturating_add(1));
         }
     }
 
     impl INonceManager for NonceManager {
         #[ink(message)]
         fn get_nonce(&self, sender: AccountId, key: [u8; 24]) -> [u8; 32] {
-            self.nonce_sequence_number.get((sender, key)).unwrap_or({
-                let mut h = [0; 32];
-                h[..24].copy_from_slice(&key);
-                h
-            })
+            let seq = self
+                .nonce_sequence_number
+                .get((sender, key))
+                .unwrap_or_default();
+            compose_nonce(key, seq)
         }
 
         #[ink(message)]
-        fn increment_nonce(&mut self, key: [u8; 24]) {
-            let mut nonce = self.get_nonce(self.env().caller(), key);
-            increment_bytes(&mut nonce);
-            self.nonce_sequence_number
-                .insert((self.env().caller(), key), &nonce);
+        fn increment_nonce(&mut self, key: [u8; 24]) -> Result<()> {
+            self.inner_increment_nonce(self.env().caller(), key);
+            Ok(())
         }
     }
-    fn increment_bytes(bytes: &mut [u8; 32]) {
-        let mut carry = true;
-        for i in (0..bytes.len()).rev() {
-            if carry {
-                if bytes[i] == 255 {
-                    bytes[i] = 0;
-                } else {
-                    bytes[i] += 1;
-                    carry = false;
-                }
-            }
+
+    /// 将完整的 nonce 拆分为 key 和序号。
+    fn split_nonce(nonce: [u8; 32]) -> ([u8; 24], u64) {
+        let mut key = [0; 24];
+        key.copy_from_slice(&nonce[..24]);
+        let mut seq = [0; 8];
+        seq.copy_from_slice(&nonce[24..]);
+        (key, u64::from_be_bytes(seq))
+    }
+
+    /// 将 key 和序号组合为完整的 nonce。
+    fn compose_nonce(key: [u8; 24], seq: u64) -> [u8; 32] {
+        let mut nonce = [0; 32];
+        nonce[..24].copy_from_slice(&key);
+        nonce[24..].copy_from_slice(&seq.to_be_bytes());
+        nonce
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink_aa::core::env::AAEnvironment;
+
+        const KEY_A: [u8; 24] = [0; 24];
+        const KEY_B: [u8; 24] = [7; 24];
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<AAEnvironment>(caller);
+        }
+
+        #[test]
+        fn split_and_compose_roundtrip() {
+            let nonce = compose_nonce(KEY_B, 0x0102_0304_0506_0708);
+            assert_eq!(&nonce[..24], &KEY_B);
+            assert_eq!(&nonce[24..], &[1, 2, 3, 4, 5, 6, 7, 8]);
+            assert_eq!(split_nonce(nonce), (KEY_B, 0x0102_0304_0506_0708));
+        }
+
+        #[ink::test]
+        fn nonce_cannot_be_replayed() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            set_caller(accounts.alice);
+            let mut nonce_manager = NonceManager::new();
+
+            let nonce = nonce_manager.get_nonce(accounts.bob, KEY_A);
+            assert_eq!(nonce, compose_nonce(KEY_A, 0));
+            assert_eq!(
+                nonce_manager.validate_and_update_nonce(accounts.bob, nonce),
+                Ok(())
+            );
+            assert_eq!(
+                nonce_manager.validate_and_update_nonce(accounts.bob, nonce),
+                Err(Error::InvalidAccountNonce)
+            );
+            assert_eq!(
+                nonce_manager.validate_and_update_nonce(accounts.bob, compose_nonce(KEY_A, 2)),
+                Err(Error::InvalidAccountNonce)
+            );
+            assert_eq!(
+                nonce_manager.get_nonce(accounts.bob, KEY_A),
+                compose_nonce(KEY_A, 1)
+            );
+        }
+
+        #[ink::test]
+        fn keys_are_independent() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            set_caller(accounts.alice);
+            let mut nonce_manager = NonceManager::new();
+
+            assert_eq!(
+                nonce_manager.validate_and_update_nonce(accounts.bob, compose_nonce(KEY_A, 0)),
+                Ok(())
+            );
+            assert_eq!(
+                nonce_manager.validate_and_update_nonce(accounts.bob, compose_nonce(KEY_B, 0)),
+                Ok(())
+            );
+            // 一个 key 下已使用的 nonce 不能在另一个 key 下重放,反之亦然。
+            assert_eq!(
+                nonce_manager.validate_and_update_nonce(accounts.bob, compose_nonce(KEY_A, 0)),
+                Err(Error::InvalidAccountNonce)
+            );
+            assert_eq!(
+                nonce_manager.validate_and_update_nonce(accounts.bob, compose_nonce(KEY_B, 0)),
+                Err(Error::InvalidAccountNonce)
+            );
+            assert_eq!(
+                nonce_manager.validate_and_update_nonce(accounts.bob, compose_nonce(KEY_A, 1)),
+                Ok(())
+            );
+            assert_eq!(
+                nonce_manager.get_nonce(accounts.bob, KEY_A),
+                compose_nonce(KEY_A, 2)
+            );
+            assert_eq!(
+                nonce_manager.get_nonce(accounts.bob, KEY_B),
+                compose_nonce(KEY_B, 1)
+            );
+        }
+
+        #[ink::test]
+        fn nonce_is_stored_under_sender() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            set_caller(accounts.alice);
+            let mut nonce_manager = NonceManager::new();
+
+            assert_eq!(
+                nonce_manager.validate_and_update_nonce(accounts.bob, compose_nonce(KEY_A, 0)),
+                Ok(())
+            );
+            assert_eq!(
+                nonce_manager.get_nonce(accounts.bob, KEY_A),
+                compose_nonce(KEY_A, 1)
+            );
+            assert_eq!(
+                nonce_manager.get_nonce(accounts.alice, KEY_A),
+                compose_nonce(KEY_A, 0)
+            );
+        }
+
+        #[ink::test]
+        fn only_owner_can_validate_and_update_nonce() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            set_caller(accounts.alice);
+            let mut nonce_manager = NonceManager::new();
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                nonce_manager.validate_and_update_nonce(accounts.bob, compose_nonce(KEY_A, 0)),
+                Err(Error::NotFromOwner)
+            );
+            assert_eq!(
+                nonce_manager.increment_nonce_of(accounts.bob, KEY_A),
+                Err(Error::NotFromOwner)
+            );
+            assert_eq!(
+                nonce_manager.get_nonce(accounts.bob, KEY_A),
+                compose_nonce(KEY_A, 0)
+            );
+        }
+
+        #[ink::test]
+        fn increment_nonce_bumps_caller_key() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            set_caller(accounts.alice);
+            let mut nonce_manager = NonceManager::new();
+
+            set_caller(accounts.bob);
+            assert_eq!(nonce_manager.increment_nonce(KEY_B), Ok(()));
+            assert_eq!(
+                nonce_manager.get_nonce(accounts.bob, KEY_B),
+                compose_nonce(KEY_B, 1)
+            );
+            assert_eq!(
+                nonce_manager.get_nonce(accounts.bob, KEY_A),
+                compose_nonce(KEY_A, 0)
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(
+                nonce_manager.increment_nonce_of(accounts.bob, KEY_B),
+                Ok(())
+            );
+            assert_eq!(
+                nonce_manager.get_nonce(accounts.bob, KEY_B),
+                compose_nonce(KEY_B, 2)
+            );
         }
     }
 }
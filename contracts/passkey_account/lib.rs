@@ -0,0 +1,361 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::passkey_account::{PasskeyAccount, PasskeyAccountRef, PasskeySignature};
+
+/// Passkey 验证器。
+///
+/// 作为 `BaseAccount` 的 `advanced` 使用,保存一个 P-256 公钥和对应的凭证 id,
+/// 使用 WebAuthn 断言验证 UserOperation,挑战(challenge)为 `user_op_hash`。
+#[ink::contract(env = ink_aa::core::env::AAEnvironment)]
+mod passkey_account {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use base_account::BaseAccountTrait;
+    use ink::prelude::vec::Vec;
+    use ink_aa::core::{
+        env::AAEnvironment,
+        error::Result,
+        helpers::{Aggregator, ValidationData},
+        user_operation::UserOperation,
+    };
+    use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+    use scale::Decode;
+    use sha2::{Digest, Sha256};
+
+    /// authenticatorData 中的用户在场(UP)标志位。
+    const FLAG_USER_PRESENT: u8 = 0x01;
+    /// authenticatorData 至少包含 rpIdHash(32) + flags(1) + signCount(4)。
+    const MIN_AUTHENTICATOR_DATA_LEN: usize = 37;
+    /// clientDataJSON 中断言的类型。
+    const WEBAUTHN_GET_TYPE: &[u8] = br#""type":"webauthn.get""#;
+
+    /// WebAuthn 断言,以 SCALE 编码的形式放在 `UserOperation::signature` 中。
+    #[derive(Clone, Debug, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct PasskeySignature {
+        /// 生成断言的凭证 id。
+        pub credential_id: Vec<u8>,
+        /// 认证器返回的 authenticatorData。
+        pub authenticator_data: Vec<u8>,
+        /// 认证器返回的 clientDataJSON 原始字节。
+        pub client_data_json: Vec<u8>,
+        /// P-256 签名,`r || s` 格式(由 DER 格式转换而来)。
+        pub signature: [u8; 64],
+    }
+
+    #[ink(storage)]
+    pub struct PasskeyAccount {
+        /// SEC1 编码的 P-256 公钥,压缩或未压缩格式均可。
+        public_key: Vec<u8>,
+        /// 注册时获得的凭证 id。
+        credential_id: Vec<u8>,
+        /// 依赖方 id 的 SHA-256 哈希,即 authenticatorData 的前 32 字节。
+        rp_id_hash: [u8; 32],
+    }
+
+    impl PasskeyAccount {
+        #[ink(constructor)]
+        pub fn new(public_key: Vec<u8>, credential_id: Vec<u8>, rp_id_hash: [u8; 32]) -> Self {
+            assert!(VerifyingKey::from_sec1_bytes(&public_key).is_ok());
+            Self {
+                public_key,
+                credential_id,
+                rp_id_hash,
+            }
+        }
+
+        #[ink(message)]
+        pub fn get_public_key(&self) -> Vec<u8> {
+            self.public_key.clone()
+        }
+
+        #[ink(message)]
+        pub fn get_credential_id(&self) -> Vec<u8> {
+            self.credential_id.clone()
+        }
+
+        /// 验证 WebAuthn 断言是否是对 `challenge` 的有效签名。
+        ///
+        /// 1. 凭证 id 与保存的一致;
+        /// 2. authenticatorData 的 rpIdHash 与保存的一致,并且设置了用户在场标志;
+        /// 3. clientDataJSON 的类型为 `webauthn.get`,挑战为 base64url(`challenge`);
+        /// 4. 签名是对 `authenticatorData || sha256(clientDataJSON)` 的有效 P-256 签名。
+        fn verify_assertion(&self, assertion: &PasskeySignature, challenge: &[u8; 32]) -> bool {
+            if assertion.credential_id != self.credential_id {
+                return false;
+            }
+
+            let authenticator_data = &assertion.authenticator_data;
+            if authenticator_data.len() < MIN_AUTHENTICATOR_DATA_LEN
+                || authenticator_data[..32] != self.rp_id_hash
+                || authenticator_data[32] & FLAG_USER_PRESENT == 0
+            {
+                return false;
+            }
+
+            let client_data_json = &assertion.client_data_json;
+            if !contains(client_data_json, WEBAUTHN_GET_TYPE)
+                || !contains(client_data_json, &challenge_field(challenge))
+            {
+                return false;
+            }
+
+            let (Ok(public_key), Ok(signature)) = (
+                VerifyingKey::from_sec1_bytes(&self.public_key),
+                Signature::from_slice(&assertion.signature),
+            ) else {
+                return false;
+            };
+            let mut message = authenticator_data.clone();
+            message.extend_from_slice(&Sha256::digest(client_data_json));
+            public_key.verify(&message, &signature).is_ok()
+        }
+    }
+
+    /// 返回 clientDataJSON 中挑战字段的内容,`"challenge":"<base64url(challenge)>"`。
+    fn challenge_field(challenge: &[u8; 32]) -> Vec<u8> {
+        // 32 字节的 base64url(无填充)编码为 43 个字符。
+        let mut encoded = [0u8; 43];
+        URL_SAFE_NO_PAD
+            .encode_slice(challenge, &mut encoded)
+            .expect("buffer is large enough");
+        let mut field = br#""challenge":""#.to_vec();
+        field.extend_from_slice(&encoded);
+        field.push(b'"');
+        field
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    impl BaseAccountTrait for PasskeyAccount {
+        /// `op.signature` 为 SCALE 编码的 `PasskeySignature`。
+        /// 断言无效时返回 `Aggregator::IllegalAggregator` 而不是回滚。
+        #[ink(message, payable)]
+        fn validate_signature(
+            &mut self,
+            op: UserOperation<AAEnvironment>,
+            user_op_hash: Hash,
+        ) -> Result<ValidationData<AAEnvironment>> {
+            let challenge: [u8; 32] = user_op_hash.as_ref().try_into().expect("hash is 32 bytes");
+            let valid = PasskeySignature::decode(&mut &op.signature[..])
+                .map(|assertion| self.verify_assertion(&assertion, &challenge))
+                .unwrap_or(false);
+            let aggregator = if valid {
+                Aggregator::NoAggregator
+            } else {
+                Aggregator::IllegalAggregator
+            };
+            Ok(ValidationData {
+                aggregator,
+                valid_after: 0,
+                valid_until: Timestamp::MAX,
+            })
+        }
+
+        /// nonce 由 EntryPoint 的 NonceManager 校验,账户不做额外限制。
+        #[ink(message)]
+        fn validate_nonce(&self, _nonce: [u8; 32]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use p256::ecdsa::{signature::Signer, SigningKey};
+        use scale::Encode;
+
+        // 按照 WebAuthn 规范的格式构造断言:RFC 6979 确定性签名,
+        // authenticatorData 与 clientDataJSON 的布局与浏览器一致。
+        const RP_ID: &[u8] = b"localhost";
+        const CREDENTIAL_ID: &[u8] = b"passkey credential";
+
+        fn signing_key() -> SigningKey {
+            SigningKey::from_bytes(&[1u8; 32].into()).unwrap()
+        }
+
+        fn account() -> PasskeyAccount {
+            let public_key = signing_key().verifying_key().to_encoded_point(true);
+            PasskeyAccount::new(
+                public_key.as_bytes().to_vec(),
+                CREDENTIAL_ID.to_vec(),
+                Sha256::digest(RP_ID).into(),
+            )
+        }
+
+        fn authenticator_data(flags: u8) -> Vec<u8> {
+            let mut data = Sha256::digest(RP_ID).to_vec();
+            data.push(flags);
+            data.extend_from_slice(&1u32.to_be_bytes());
+            data
+        }
+
+        fn client_data_json(ty: &str, challenge: &[u8; 32]) -> Vec<u8> {
+            format!(
+                r#"{{"type":"{}","challenge":"{}","origin":"http://localhost:8080","crossOrigin":false}}"#,
+                ty,
+                URL_SAFE_NO_PAD.encode(challenge),
+            )
+            .into_bytes()
+        }
+
+        fn assertion(authenticator_data: Vec<u8>, client_data_json: Vec<u8>) -> PasskeySignature {
+            let mut message = authenticator_data.clone();
+            message.extend_from_slice(&Sha256::digest(&client_data_json));
+            let signature: Signature = signing_key().sign(&message);
+            PasskeySignature {
+                credential_id: CREDENTIAL_ID.to_vec(),
+                authenticator_data,
+                client_data_json,
+                signature: signature.to_bytes().into(),
+            }
+        }
+
+        fn user_op(signature: Vec<u8>) -> UserOperation<AAEnvironment> {
+            UserOperation {
+                signature,
+                ..Default::default()
+            }
+        }
+
+        fn validate(account: &mut PasskeyAccount, signature: Vec<u8>, hash: Hash) -> Aggregator {
+            account
+                .validate_signature(user_op(signature), hash)
+                .unwrap()
+                .aggregator
+        }
+
+        #[ink::test]
+        fn valid_assertion_is_accepted() {
+            let mut account = account();
+            let hash = Hash::from([7; 32]);
+            let signature = assertion(
+                authenticator_data(FLAG_USER_PRESENT),
+                client_data_json("webauthn.get", &[7; 32]),
+            );
+
+            assert_eq!(
+                validate(&mut account, signature.encode(), hash),
+                Aggregator::NoAggregator
+            );
+        }
+
+        /// 浏览器中的 passkey 对挑战
+        /// `0xf631058a3ba1116acce12396fad0a125b5041c43f8e15723709f81aa8d5f4ccf` 生成的断言,
+        /// 依赖方为 `localhost`,设置了用户在场和用户验证标志。
+        #[ink::test]
+        fn browser_assertion_is_accepted() {
+            let public_key = hex::decode(
+                "04\
+                 3f2be075ef57d6c8374ef412fe54fdd980050f70f4f3a00b5b1b32d2def7d28d\
+                 57095a365acc2590ade3583fabfe8fbd64a9ed3ec07520da00636fb21f0176c1",
+            )
+            .unwrap();
+            let mut account = PasskeyAccount::new(
+                public_key,
+                CREDENTIAL_ID.to_vec(),
+                Sha256::digest(RP_ID).into(),
+            );
+            let challenge: [u8; 32] =
+                hex::decode("f631058a3ba1116acce12396fad0a125b5041c43f8e15723709f81aa8d5f4ccf")
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+            let client_data_json = br#"{"type":"webauthn.get","challenge":"9jEFijuhEWrM4SOW-tChJbUEHEP44VcjcJ-Bqo1fTM8","origin":"http://localhost:3005"}"#;
+            let signature = PasskeySignature {
+                credential_id: CREDENTIAL_ID.to_vec(),
+                authenticator_data: hex::decode(
+                    "49960de5880e8c687434170f6476605b8fe4aeb9a28632c7995cf3ba831d97630500000101",
+                )
+                .unwrap(),
+                client_data_json: client_data_json.to_vec(),
+                signature: hex::decode(
+                    "60946081650523acad13c8eff94996a409b1ed60e923c90f9e366aad619adffa\
+                     3216a237b73765d01b839e0832d73474bc7e63f4c86ef05fbbbfbeb34b35602b",
+                )
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            };
+
+            assert!(contains(client_data_json, WEBAUTHN_GET_TYPE));
+            assert!(contains(client_data_json, &challenge_field(&challenge)));
+            assert_eq!(
+                validate(&mut account, signature.encode(), Hash::from(challenge)),
+                Aggregator::NoAggregator
+            );
+            // 同一断言不能用于其他用户操作。
+            assert_eq!(
+                validate(&mut account, signature.encode(), Hash::from([7; 32])),
+                Aggregator::IllegalAggregator
+            );
+        }
+
+        #[ink::test]
+        fn invalid_assertions_are_rejected() {
+            let mut account = account();
+            let hash = Hash::from([7; 32]);
+
+            // 挑战不是 user_op_hash。
+            let signature = assertion(
+                authenticator_data(FLAG_USER_PRESENT),
+                client_data_json("webauthn.get", &[8; 32]),
+            );
+            assert_eq!(
+                validate(&mut account, signature.encode(), hash),
+                Aggregator::IllegalAggregator
+            );
+
+            // 注册时的断言类型。
+            let signature = assertion(
+                authenticator_data(FLAG_USER_PRESENT),
+                client_data_json("webauthn.create", &[7; 32]),
+            );
+            assert_eq!(
+                validate(&mut account, signature.encode(), hash),
+                Aggregator::IllegalAggregator
+            );
+
+            // 没有设置用户在场标志。
+            let signature = assertion(
+                authenticator_data(0),
+                client_data_json("webauthn.get", &[7; 32]),
+            );
+            assert_eq!(
+                validate(&mut account, signature.encode(), hash),
+                Aggregator::IllegalAggregator
+            );
+
+            // 签名之后篡改了 authenticatorData。
+            let mut signature = assertion(
+                authenticator_data(FLAG_USER_PRESENT),
+                client_data_json("webauthn.get", &[7; 32]),
+            );
+            signature.authenticator_data[36] = 2;
+            assert_eq!(
+                validate(&mut account, signature.encode(), hash),
+                Aggregator::IllegalAggregator
+            );
+
+            // 其他凭证。
+            let mut signature = assertion(
+                authenticator_data(FLAG_USER_PRESENT),
+                client_data_json("webauthn.get", &[7; 32]),
+            );
+            signature.credential_id = b"other credential".to_vec();
+            assert_eq!(
+                validate(&mut account, signature.encode(), hash),
+                Aggregator::IllegalAggregator
+            );
+
+            // 无法解码的签名。
+            assert_eq!(
+                validate(&mut account, vec![1, 2, 3], hash),
+                Aggregator::IllegalAggregator
+            );
+        }
+    }
+}
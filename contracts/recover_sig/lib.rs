@@ -1,15 +1,26 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-pub use self::recover_sig::{ConfirmationStatus, RecoverSig, RecoverSigRef};
+pub use self::recover_sig::{
+    Asset, ConfirmationStatus, RecoverSig, RecoverSigRef, Recovery, SessionKey, SpendingLimit,
+};
 
 #[ink::contract(env = ink_aa::core::env::AAEnvironment)]
 mod recover_sig {
     use base_account::BaseAccountTrait;
     use ink::{prelude::vec::Vec, storage::Mapping};
     use ink_aa::core::user_operation::UserOperation;
-    use ink_aa::core::{env::AAEnvironment, helpers::ValidationData};
-    use ink_aa::core::{error::Result, helpers::Aggregator};
+    use ink_aa::core::{env::AAEnvironment, exec::Transaction, helpers::ValidationData};
+    use ink_aa::core::{
+        error::{Error, Result},
+        helpers::Aggregator,
+        signature::MultiSignature,
+    };
+    use ink_aa::traits::psp22::{APPROVE_SELECTOR, INCREASE_ALLOWANCE_SELECTOR, TRANSFER_SELECTOR};
+    use scale::Decode;
     const MAX_OWNERS: u32 = 50;
+    const MAX_GUARDIANS: u32 = 50;
+    /// 默认的恢复延迟,2 天(毫秒)。
+    const DEFAULT_RECOVERY_DELAY: Timestamp = 2 * 24 * 60 * 60 * 1000;
 
     #[derive(Clone, Copy, scale::Decode, scale::Encode)]
     #[cfg_attr(
@@ -46,12 +57,235 @@ mod recover_sig {
         new_requirement: u32,
     }
 
+    /// Emitted when a guardian is added to the wallet.
+    #[ink(event)]
+    pub struct GuardianAddition {
+        /// The guardian that was added.
+        #[ink(topic)]
+        guardian: AccountId,
+    }
+
+    /// Emitted when a guardian is removed from the wallet.
+    #[ink(event)]
+    pub struct GuardianRemoval {
+        /// The guardian that was removed.
+        #[ink(topic)]
+        guardian: AccountId,
+    }
+
+    /// Emitted when the guardian threshold or the recovery delay changed.
+    #[ink(event)]
+    pub struct RecoveryConfigChange {
+        /// The number of guardians required to execute a recovery.
+        guardian_threshold: u32,
+        /// The delay between initiating and executing a recovery.
+        recovery_delay: Timestamp,
+    }
+
+    /// Emitted when a guardian initiates a recovery.
+    #[ink(event)]
+    pub struct RecoveryInitiation {
+        /// The recovery id.
+        #[ink(topic)]
+        id: u32,
+        /// The guardian that initiated the recovery.
+        #[ink(topic)]
+        guardian: AccountId,
+        /// The owners after the recovery.
+        new_owners: Vec<AccountId>,
+        /// The requirement after the recovery.
+        new_requirement: u32,
+    }
+
+    /// Emitted when a guardian supports a recovery.
+    #[ink(event)]
+    pub struct RecoverySupport {
+        /// The recovery id.
+        #[ink(topic)]
+        id: u32,
+        /// The guardian that supported the recovery.
+        #[ink(topic)]
+        guardian: AccountId,
+    }
+
+    /// Emitted when an owner cancels a recovery.
+    #[ink(event)]
+    pub struct RecoveryCancellation {
+        /// The recovery id.
+        #[ink(topic)]
+        id: u32,
+    }
+
+    /// Emitted when a recovery is executed and the owners are replaced.
+    #[ink(event)]
+    pub struct RecoveryExecution {
+        /// The recovery id.
+        #[ink(topic)]
+        id: u32,
+    }
+
+    /// Emitted when a session key is registered or replaced.
+    #[ink(event)]
+    pub struct SessionKeyRegistration {
+        /// The session key that was registered.
+        #[ink(topic)]
+        key: AccountId,
+        /// The start of the session.
+        valid_after: Timestamp,
+        /// The end of the session.
+        valid_until: Timestamp,
+    }
+
+    /// Emitted when a session key is revoked.
+    #[ink(event)]
+    pub struct SessionKeyRevocation {
+        /// The session key that was revoked.
+        #[ink(topic)]
+        key: AccountId,
+    }
+
+    /// 会话密钥及其权限。
+    ///
+    /// 会话密钥可以在有效期内单独签名用户操作,
+    /// 但只能调用允许的合约方法,并且花费不能超过上限。
+    #[derive(Clone, Debug, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct SessionKey {
+        /// 允许调用的 (合约地址, 方法选择器)。
+        pub permissions: Vec<(AccountId, [u8; 4])>,
+        /// 剩余可以花费的金额,每次验证时扣除用户操作需要预付的最大费用
+        /// 以及交易转出的原生代币。
+        pub spending_limit: Balance,
+        /// 会话的开始时间戳。
+        pub valid_after: Timestamp,
+        /// 会话的截止时间戳。
+        pub valid_until: Timestamp,
+    }
+
+    /// Emitted when the spending limit of an asset changed.
+    #[ink(event)]
+    pub struct SpendingLimitChange {
+        /// The limited asset.
+        #[ink(topic)]
+        asset: Asset,
+        /// The new limit, `None` if the limit was removed.
+        limit: Option<SpendingLimit>,
+    }
+
+    /// Emitted when the policy for ops over the spending limit changed.
+    #[ink(event)]
+    pub struct OverLimitPolicyChange {
+        /// The number of owner signatures required for ops over the limit.
+        over_limit_requirement: u32,
+        /// The delay for scheduled ops over the limit.
+        over_limit_delay: Timestamp,
+    }
+
+    /// Emitted when an owner schedules an op over the spending limit.
+    #[ink(event)]
+    pub struct OverLimitOpSchedule {
+        /// The hash of the scheduled user operation.
+        #[ink(topic)]
+        user_op_hash: Hash,
+        /// The first timestamp the op is valid.
+        valid_after: Timestamp,
+    }
+
+    /// Emitted when an owner cancels a scheduled op over the spending limit.
+    #[ink(event)]
+    pub struct OverLimitOpCancellation {
+        /// The hash of the cancelled user operation.
+        #[ink(topic)]
+        user_op_hash: Hash,
+    }
+
+    /// (周期序号, 该周期内已花费的数量)。
+    type PeriodSpent = (u64, Balance);
+
+    /// 受花费限额约束的资产。
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Asset {
+        /// 原生代币,即 `Transaction::transferred_value`。
+        Native,
+        /// PSP22 代币合约,统计 `PSP22::transfer` 转出的数量,
+        /// 以及 `PSP22::approve` 和 `PSP22::increase_allowance` 授权的数量。
+        Token(AccountId),
+    }
+
+    /// 每个周期内某种资产的花费上限。
+    #[derive(Clone, Debug, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct SpendingLimit {
+        /// 每个周期最多可以花费的数量。
+        pub limit: Balance,
+        /// 周期的长度(毫秒),周期按 `block_timestamp / period` 对齐,
+        /// 例如 `86_400_000` 表示按 UTC 自然日计算。
+        pub period: Timestamp,
+    }
+
+    /// 待执行的恢复请求。
+    #[derive(Clone, Debug, PartialEq, Eq, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Recovery {
+        pub id: u32,
+        /// 恢复后的所有者,已排序去重。
+        pub new_owners: Vec<AccountId>,
+        /// 恢复后的签名数量要求。
+        pub new_requirement: u32,
+        /// 支持此恢复的监护人,包括发起者。
+        pub supporters: Vec<AccountId>,
+        /// 发起恢复的时间戳。
+        pub initiated_at: Timestamp,
+    }
+
     #[ink(storage)]
     #[derive(Default)]
     pub struct RecoverSig {
         owners: Vec<AccountId>,
         is_owner: Mapping<AccountId, ()>,
         requirement: u32,
+        /// 监护人,与所有者分开管理,只能参与恢复,不能签名用户操作。
+        guardians: Vec<AccountId>,
+        is_guardian: Mapping<AccountId, ()>,
+        /// 执行恢复所需的监护人数量。
+        guardian_threshold: u32,
+        /// 发起恢复后需要等待的时间,在此期间所有者可以取消恢复。
+        recovery_delay: Timestamp,
+        /// 当前待执行的恢复请求。
+        recovery: Option<Recovery>,
+        /// 下一个恢复请求的 id。
+        next_recovery_id: u32,
+        /// 已注册的会话密钥,按注册时的 `epoch` 存储。
+        session_keys: Mapping<(u32, AccountId), SessionKey>,
+        /// 每种资产的花费限额。
+        spending_limits: Mapping<Asset, SpendingLimit>,
+        /// 每种资产已经花费的数量。
+        spent: Mapping<Asset, PeriodSpent>,
+        /// 超出限额的用户操作所需的所有者签名数量,0 表示不能通过更多签名超出限额。
+        over_limit_requirement: u32,
+        /// 预约的超出限额的用户操作需要等待的时间,0 表示不能通过预约超出限额。
+        over_limit_delay: Timestamp,
+        /// 预约的超出限额的用户操作,(epoch, user_op_hash) -> 预约的时间戳。
+        scheduled_ops: Mapping<(u32, Hash), Timestamp>,
+        /// 每次执行恢复时递增,使之前的所有者注册的会话密钥和预约全部失效。
+        epoch: u32,
+        /// 部署者,绑定账户之后清空。
+        deployer: Option<AccountId>,
+        /// 使用此验证器的账户(`BaseAccount`),钱包的配置只能由它修改。
+        account: Option<AccountId>,
     }
 
     impl RecoverSig {
@@ -68,9 +302,55 @@ mod recover_sig {
 
             contract.owners = owners;
             contract.requirement = requirement;
+            contract.recovery_delay = DEFAULT_RECOVERY_DELAY;
+            contract.deployer = Some(Self::env().caller());
+            contract
+        }
+
+        /// 创建带有监护人的钱包。
+        ///
+        /// - `guardian_threshold` 执行恢复所需的监护人数量
+        /// - `recovery_delay` 发起恢复后到可以执行恢复的等待时间(毫秒)
+        #[ink(constructor)]
+        pub fn with_guardians(
+            requirement: u32,
+            owners: Vec<AccountId>,
+            mut guardians: Vec<AccountId>,
+            guardian_threshold: u32,
+            recovery_delay: Timestamp,
+        ) -> Self {
+            let mut contract = Self::new(requirement, owners);
+            guardians.sort_unstable();
+            guardians.dedup();
+            ensure_guardian_threshold_is_valid(guardians.len() as u32, guardian_threshold);
+
+            for guardian in &guardians {
+                contract.is_guardian.insert(guardian, &());
+            }
+
+            contract.guardians = guardians;
+            contract.guardian_threshold = guardian_threshold;
+            contract.recovery_delay = recovery_delay;
             contract
         }
 
+        /// 由部署者绑定使用此验证器的账户,只能绑定一次。
+        ///
+        /// 账户的地址取决于验证器的地址,所以不能在构造时传入。
+        /// 绑定之后,所有者通过账户执行的用户操作修改钱包的配置,
+        /// 因此配置的修改同样需要 `requirement` 个所有者签名。
+        #[ink(message)]
+        pub fn set_account(&mut self, account: AccountId) {
+            assert_eq!(Some(self.env().caller()), self.deployer);
+            self.deployer = None;
+            self.account = Some(account);
+        }
+
+        #[ink(message)]
+        pub fn get_account(&self) -> Option<AccountId> {
+            self.account
+        }
+
         #[ink(message)]
         pub fn add_owner(&mut self, new_owner: AccountId) {
             self.ensure_from_wallet();
@@ -92,6 +372,7 @@ mod recover_sig {
             self.owners.swap_remove(owner_index);
             self.is_owner.remove(owner);
             self.requirement = requirement;
+            self.clamp_over_limit_requirement();
             self.env().emit_event(OwnerRemoval { owner });
         }
 
@@ -113,9 +394,435 @@ mod recover_sig {
             self.ensure_from_wallet();
             ensure_requirement_is_valid(self.owners.len() as u32, new_requirement);
             self.requirement = new_requirement;
+            self.clamp_over_limit_requirement();
             self.env().emit_event(RequirementChange { new_requirement });
         }
 
+        #[ink(message)]
+        pub fn add_guardian(&mut self, new_guardian: AccountId) {
+            self.ensure_from_wallet();
+            assert!(!self.is_guardian.contains(new_guardian));
+            let threshold = if self.guardian_threshold == 0 {
+                1
+            } else {
+                self.guardian_threshold
+            };
+            ensure_guardian_threshold_is_valid(self.guardians.len() as u32 + 1, threshold);
+            self.is_guardian.insert(new_guardian, &());
+            self.guardians.push(new_guardian);
+            self.guardian_threshold = threshold;
+            self.env().emit_event(GuardianAddition {
+                guardian: new_guardian,
+            });
+        }
+
+        #[ink(message)]
+        pub fn remove_guardian(&mut self, guardian: AccountId) {
+            self.ensure_from_wallet();
+            self.ensure_guardian(&guardian);
+            let len = self.guardians.len() as u32 - 1;
+            let threshold = u32::min(len, self.guardian_threshold);
+            ensure_guardian_threshold_is_valid(len, threshold);
+            let index = self
+                .guardians
+                .iter()
+                .position(|x| *x == guardian)
+                .expect("guardian was verified above");
+            self.guardians.swap_remove(index);
+            self.is_guardian.remove(guardian);
+            self.guardian_threshold = threshold;
+            self.env().emit_event(GuardianRemoval { guardian });
+        }
+
+        #[ink(message)]
+        pub fn change_recovery_config(
+            &mut self,
+            guardian_threshold: u32,
+            recovery_delay: Timestamp,
+        ) {
+            self.ensure_from_wallet();
+            ensure_guardian_threshold_is_valid(self.guardians.len() as u32, guardian_threshold);
+            self.guardian_threshold = guardian_threshold;
+            self.recovery_delay = recovery_delay;
+            self.env().emit_event(RecoveryConfigChange {
+                guardian_threshold,
+                recovery_delay,
+            });
+        }
+
+        #[ink(message)]
+        pub fn get_guardians(&self) -> Vec<AccountId> {
+            self.guardians.clone()
+        }
+
+        #[ink(message)]
+        pub fn get_recovery(&self) -> Option<Recovery> {
+            self.recovery.clone()
+        }
+
+        /// 由监护人发起恢复,发起者自动支持该恢复,返回恢复 id。
+        ///
+        /// 已有待执行的恢复时不能发起新的恢复,需要所有者先取消它,
+        /// 除非它的支持者都已不是监护人。
+        #[ink(message)]
+        pub fn initiate_recovery(
+            &mut self,
+            new_owners: Vec<AccountId>,
+            new_requirement: u32,
+        ) -> u32 {
+            let guardian = self.env().caller();
+            self.ensure_guardian(&guardian);
+            if let Some(pending) = &self.recovery {
+                assert_eq!(self.count_supporters(pending), 0);
+            }
+            let mut new_owners = new_owners;
+            new_owners.sort_unstable();
+            new_owners.dedup();
+            ensure_requirement_is_valid(new_owners.len() as u32, new_requirement);
+
+            let id = self.next_recovery_id;
+            self.next_recovery_id = id.checked_add(1).expect("recovery id overflow");
+            self.recovery = Some(Recovery {
+                id,
+                new_owners: new_owners.clone(),
+                new_requirement,
+                supporters: Vec::from([guardian]),
+                initiated_at: self.env().block_timestamp(),
+            });
+            self.env().emit_event(RecoveryInitiation {
+                id,
+                guardian,
+                new_owners,
+                new_requirement,
+            });
+            id
+        }
+
+        /// 由监护人支持 id 为 `id` 的恢复。
+        #[ink(message)]
+        pub fn support_recovery(&mut self, id: u32) {
+            let guardian = self.env().caller();
+            self.ensure_guardian(&guardian);
+            let recovery = self.ensure_pending_recovery(id);
+            assert!(!recovery.supporters.contains(&guardian));
+            recovery.supporters.push(guardian);
+            self.env().emit_event(RecoverySupport { id, guardian });
+        }
+
+        /// 由所有者取消待执行的恢复。
+        #[ink(message)]
+        pub fn cancel_recovery(&mut self) {
+            self.ensure_caller_is_owner();
+            let recovery = self.recovery.take().expect("no pending recovery");
+            self.env()
+                .emit_event(RecoveryCancellation { id: recovery.id });
+        }
+
+        /// 在足够多的监护人支持并且等待时间已过后,任何人都可以执行恢复,
+        /// 用新的所有者替换原有的所有者。
+        /// 原有的所有者注册的会话密钥和预约的用户操作随之失效。
+        #[ink(message)]
+        pub fn execute_recovery(&mut self, id: u32) {
+            let recovery = self.ensure_pending_recovery(id).clone();
+            let supporters = self.count_supporters(&recovery);
+            assert!(self.guardian_threshold > 0 && supporters >= self.guardian_threshold);
+            assert!(
+                self.env().block_timestamp()
+                    >= recovery.initiated_at.saturating_add(self.recovery_delay)
+            );
+
+            for owner in &self.owners {
+                self.is_owner.remove(owner);
+            }
+            for owner in &recovery.new_owners {
+                self.is_owner.insert(owner, &());
+            }
+            self.owners = recovery.new_owners;
+            self.requirement = recovery.new_requirement;
+            self.clamp_over_limit_requirement();
+            self.epoch = self.epoch.checked_add(1).expect("epoch overflow");
+            self.recovery = None;
+            self.env().emit_event(RecoveryExecution { id });
+        }
+
+        /// 注册会话密钥,已存在时替换其权限。
+        /// 由账户调用,即需要 `requirement` 个所有者签名的用户操作。
+        #[ink(message)]
+        pub fn register_session_key(&mut self, key: AccountId, session_key: SessionKey) {
+            self.ensure_from_wallet();
+            self.ensure_no_owner(&key);
+            assert!(session_key.valid_after < session_key.valid_until);
+            self.session_keys.insert((self.epoch, key), &session_key);
+            self.env().emit_event(SessionKeyRegistration {
+                key,
+                valid_after: session_key.valid_after,
+                valid_until: session_key.valid_until,
+            });
+        }
+
+        /// 由所有者撤销会话密钥。
+        #[ink(message)]
+        pub fn revoke_session_key(&mut self, key: AccountId) {
+            self.ensure_caller_is_owner();
+            assert!(self.session_keys.contains((self.epoch, key)));
+            self.session_keys.remove((self.epoch, key));
+            self.env().emit_event(SessionKeyRevocation { key });
+        }
+
+        #[ink(message)]
+        pub fn get_session_key(&self, key: AccountId) -> Option<SessionKey> {
+            self.session_keys.get((self.epoch, key))
+        }
+
+        /// 设置资产的花费限额,`None` 表示取消限额。
+        #[ink(message)]
+        pub fn set_spending_limit(&mut self, asset: Asset, limit: Option<SpendingLimit>) {
+            self.ensure_from_wallet();
+            match &limit {
+                Some(limit) => {
+                    assert!(limit.period > 0);
+                    self.spending_limits.insert(asset, limit);
+                }
+                None => self.spending_limits.remove(asset),
+            }
+            self.spent.remove(asset);
+            self.env().emit_event(SpendingLimitChange { asset, limit });
+        }
+
+        /// 设置超出限额的用户操作的处理方式。
+        ///
+        /// - `over_limit_requirement` 超出限额时所需的签名数量,0 表示禁用,
+        ///   否则不能小于 `requirement`
+        /// - `over_limit_delay` 预约后需要等待的时间,0 表示禁用
+        #[ink(message)]
+        pub fn set_over_limit_policy(
+            &mut self,
+            over_limit_requirement: u32,
+            over_limit_delay: Timestamp,
+        ) {
+            self.ensure_from_wallet();
+            assert!(
+                over_limit_requirement == 0
+                    || (self.requirement <= over_limit_requirement
+                        && over_limit_requirement <= self.owners.len() as u32)
+            );
+            self.over_limit_requirement = over_limit_requirement;
+            self.over_limit_delay = over_limit_delay;
+            self.env().emit_event(OverLimitPolicyChange {
+                over_limit_requirement,
+                over_limit_delay,
+            });
+        }
+
+        /// 预约一个超出限额的用户操作,
+        /// 等待 `over_limit_delay` 后,它只需要 `requirement` 个签名即可执行。
+        /// 由账户调用,即预约本身同样需要 `requirement` 个所有者签名,
+        /// 在等待期间任何一个所有者都可以取消预约。
+        #[ink(message)]
+        pub fn schedule_over_limit_op(&mut self, user_op_hash: Hash) {
+            self.ensure_from_wallet();
+            assert!(self.over_limit_delay > 0);
+            let now = self.env().block_timestamp();
+            self.scheduled_ops.insert((self.epoch, user_op_hash), &now);
+            self.env().emit_event(OverLimitOpSchedule {
+                user_op_hash,
+                valid_after: now.saturating_add(self.over_limit_delay),
+            });
+        }
+
+        /// 由所有者取消预约的超出限额的用户操作。
+        #[ink(message)]
+        pub fn cancel_over_limit_op(&mut self, user_op_hash: Hash) {
+            self.ensure_caller_is_owner();
+            assert!(self.scheduled_ops.contains((self.epoch, user_op_hash)));
+            self.scheduled_ops.remove((self.epoch, user_op_hash));
+            self.env()
+                .emit_event(OverLimitOpCancellation { user_op_hash });
+        }
+
+        #[ink(message)]
+        pub fn get_spending_limit(&self, asset: Asset) -> Option<SpendingLimit> {
+            self.spending_limits.get(asset)
+        }
+
+        /// 返回资产在当前周期内已经花费的数量。
+        #[ink(message)]
+        pub fn get_spent(&self, asset: Asset) -> Balance {
+            let Some(limit) = self.spending_limits.get(asset) else {
+                return 0;
+            };
+            match self.spent.get(asset) {
+                Some((period, spent)) if period == self.env().block_timestamp() / limit.period => {
+                    spent
+                }
+                _ => 0,
+            }
+        }
+
+        /// 计算执行用户操作后每种受限资产在当前周期内的花费,
+        /// 返回 (更新后的花费, 是否没有超出限额)。
+        fn track_spending(
+            &self,
+            op: &UserOperation<AAEnvironment>,
+        ) -> (Vec<(Asset, PeriodSpent)>, bool) {
+            let now = self.env().block_timestamp();
+            let mut updates = Vec::new();
+            let mut within_limit = true;
+            for (asset, amount) in op_spending(op) {
+                let Some(limit) = self.spending_limits.get(asset) else {
+                    continue;
+                };
+                let period = now / limit.period;
+                let spent = match self.spent.get(asset) {
+                    Some((spent_period, spent)) if spent_period == period => spent,
+                    _ => 0,
+                };
+                let spent = spent.saturating_add(amount);
+                within_limit &= spent <= limit.limit;
+                updates.push((asset, (period, spent)));
+            }
+            (updates, within_limit)
+        }
+
+        /// 使用所有者的签名验证用户操作。
+        ///
+        /// 超出花费限额时需要 `over_limit_requirement` 个签名,
+        /// 或者已经被预约,此时返回的 `valid_after` 为预约时间加上 `over_limit_delay`。
+        fn validate_owners(
+            &mut self,
+            signatures: Vec<(AccountId, MultiSignature)>,
+            user_op_hash: &Hash,
+            within_limit: bool,
+        ) -> ValidationData<AAEnvironment> {
+            let approvals = self.count_approvals(signatures, user_op_hash);
+            let scheduled_at = self
+                .scheduled_ops
+                .get((self.epoch, *user_op_hash))
+                .filter(|_| self.over_limit_delay > 0);
+            let (valid, valid_after) = if approvals < self.requirement {
+                (false, 0)
+            } else if within_limit
+                || (self.over_limit_requirement > 0 && approvals >= self.over_limit_requirement)
+            {
+                (true, 0)
+            } else if let Some(scheduled_at) = scheduled_at {
+                (true, scheduled_at.saturating_add(self.over_limit_delay))
+            } else {
+                (false, 0)
+            };
+            ValidationData {
+                aggregator: if valid {
+                    Aggregator::NoAggregator
+                } else {
+                    Aggregator::IllegalAggregator
+                },
+                valid_after,
+                valid_until: Timestamp::MAX,
+            }
+        }
+
+        /// 使用会话密钥验证用户操作,验证通过时扣除花费。
+        /// 会话密钥不能超出账户的花费限额。
+        /// 返回的时间范围为会话的有效期。
+        fn validate_session_key(
+            &mut self,
+            op: &UserOperation<AAEnvironment>,
+            user_op_hash: &Hash,
+            key: AccountId,
+            signature: &MultiSignature,
+            mut session_key: SessionKey,
+            within_limit: bool,
+        ) -> ValidationData<AAEnvironment> {
+            let transactions = op.transactions();
+            // 有 paymaster 时账户不需要预付费用。
+            let prefund = if op.paymaster_and_data.is_eq_zero() {
+                op.required_prefund().map(Balance::from)
+            } else {
+                Some(0)
+            };
+            let cost = transactions.iter().fold(prefund, |cost, transaction| {
+                cost.and_then(|cost| cost.checked_add(transaction.transferred_value))
+            });
+            let remaining = cost.and_then(|cost| session_key.spending_limit.checked_sub(cost));
+
+            let valid = within_limit
+                && signature.verify(user_op_hash.as_ref(), &key)
+                && transactions.iter().all(|transaction| {
+                    session_key
+                        .permissions
+                        .contains(&(transaction.callee, transaction.selector))
+                });
+            let aggregator = match remaining {
+                Some(remaining) if valid => {
+                    session_key.spending_limit = remaining;
+                    self.session_keys.insert((self.epoch, key), &session_key);
+                    Aggregator::NoAggregator
+                }
+                _ => Aggregator::IllegalAggregator,
+            };
+            ValidationData {
+                aggregator,
+                valid_after: session_key.valid_after,
+                valid_until: session_key.valid_until,
+            }
+        }
+
+        /// 所有者或 `requirement` 变化后,保持 `requirement <= over_limit_requirement <= owners`,
+        /// 0 表示禁用,保持不变。
+        fn clamp_over_limit_requirement(&mut self) {
+            if self.over_limit_requirement == 0 {
+                return;
+            }
+            let over_limit_requirement = self
+                .over_limit_requirement
+                .clamp(self.requirement, self.owners.len() as u32);
+            if over_limit_requirement != self.over_limit_requirement {
+                self.over_limit_requirement = over_limit_requirement;
+                self.env().emit_event(OverLimitPolicyChange {
+                    over_limit_requirement,
+                    over_limit_delay: self.over_limit_delay,
+                });
+            }
+        }
+
+        /// Panics if there is no pending recovery with the given `id`.
+        fn ensure_pending_recovery(&mut self, id: u32) -> &mut Recovery {
+            let recovery = self.recovery.as_mut().expect("no pending recovery");
+            assert_eq!(recovery.id, id);
+            recovery
+        }
+
+        /// 返回恢复的支持者中仍然是监护人的数量。
+        fn count_supporters(&self, recovery: &Recovery) -> u32 {
+            recovery
+                .supporters
+                .iter()
+                .filter(|guardian| self.is_guardian.contains(*guardian))
+                .count() as u32
+        }
+
+        fn ensure_guardian(&self, guardian: &AccountId) {
+            assert!(self.is_guardian.contains(guardian));
+        }
+
+        /// 返回对 `user_op_hash` 签名有效的不同所有者的数量。
+        fn count_approvals(
+            &self,
+            signatures: Vec<(AccountId, MultiSignature)>,
+            user_op_hash: &Hash,
+        ) -> u32 {
+            let mut approved: Vec<AccountId> = Vec::new();
+            for (owner, signature) in signatures {
+                if !approved.contains(&owner)
+                    && self.is_owner.contains(owner)
+                    && signature.verify(user_op_hash.as_ref(), &owner)
+                {
+                    approved.push(owner);
+                }
+            }
+            approved.len() as u32
+        }
+
         /// Panics if `owner` is not found in `self.owners`.
         fn owner_index(&self, owner: &AccountId) -> u32 {
             self.owners.iter().position(|x| *x == *owner).expect(
@@ -128,8 +835,9 @@ mod recover_sig {
             self.ensure_owner(&self.env().caller());
         }
 
+        /// Panics if the caller is not the account bound by `set_account`.
         fn ensure_from_wallet(&self) {
-            assert_eq!(self.env().caller(), self.env().account_id());
+            assert_eq!(Some(self.env().caller()), self.account);
         }
 
         fn ensure_owner(&self, owner: &AccountId) {
@@ -145,24 +853,830 @@ mod recover_sig {
         assert!(0 < requirement && requirement <= owners && owners <= MAX_OWNERS);
     }
 
+    /// 返回用户操作花费的资产和数量,同一种资产合并计算。
+    fn op_spending(op: &UserOperation<AAEnvironment>) -> Vec<(Asset, Balance)> {
+        let mut spending: Vec<(Asset, Balance)> = Vec::new();
+        for transaction in op.transactions() {
+            for (asset, amount) in transaction_spending(&transaction) {
+                match spending.iter_mut().find(|(spent, _)| *spent == asset) {
+                    Some((_, total)) => *total = total.saturating_add(amount),
+                    None => spending.push((asset, amount)),
+                }
+            }
+        }
+        spending
+    }
+
+    /// 返回一笔交易花费的资产:转账的原生代币,通过 `PSP22::transfer` 转出的代币,
+    /// 以及授权给其他账户的代币,因为被授权者可以随后通过 `transfer_from` 转出。
+    fn transaction_spending(transaction: &Transaction<AAEnvironment>) -> Vec<(Asset, Balance)> {
+        let mut spending = Vec::new();
+        if transaction.transferred_value > 0 {
+            spending.push((Asset::Native, transaction.transferred_value));
+        }
+        let input = &mut &transaction.input[..];
+        let value = match transaction.selector {
+            TRANSFER_SELECTOR => <(AccountId, Balance, Vec<u8>)>::decode(input)
+                .ok()
+                .map(|(_to, value, _data)| value),
+            APPROVE_SELECTOR | INCREASE_ALLOWANCE_SELECTOR => <(AccountId, Balance)>::decode(input)
+                .ok()
+                .map(|(_spender, value)| value),
+            _ => None,
+        };
+        if let Some(value) = value {
+            spending.push((Asset::Token(transaction.callee), value));
+        }
+        spending
+    }
+
+    /// 没有监护人时阈值必须为 0,即不能进行恢复。
+    fn ensure_guardian_threshold_is_valid(guardians: u32, threshold: u32) {
+        assert!(threshold <= guardians && guardians <= MAX_GUARDIANS);
+        assert!(guardians == 0 || threshold > 0);
+    }
+
     impl BaseAccountTrait for RecoverSig {
+        /// `op.signature` 为 SCALE 编码的 `Vec<(AccountId, MultiSignature)>`,
+        /// 至少需要 `requirement` 个不同的所有者对 `user_op_hash` 签名,
+        /// 或者只有一个已注册的会话密钥的签名。
+        /// 超出花费限额时的处理见 `validate_owners`。
+        /// 签名无效时返回 `Aggregator::IllegalAggregator` 而不是回滚。
+        ///
+        /// 验证通过时会记录花费并消耗预约,所以只能由绑定的账户在验证用户操作时调用。
         #[ink(message, payable)]
         fn validate_signature(
-            &self,
-            _op: UserOperation<AAEnvironment>,
-            _user_op_hash: Hash,
+            &mut self,
+            op: UserOperation<AAEnvironment>,
+            user_op_hash: Hash,
         ) -> Result<ValidationData<AAEnvironment>> {
-            // TODO:
-            Ok(ValidationData {
-                aggregator: Aggregator::NoAggregator,
-                valid_after: self.env().block_timestamp(),
-                valid_until: self.env().block_timestamp() + 5000,
-            })
+            if Some(self.env().caller()) != self.account {
+                return Err(Error::NotFromAccount);
+            }
+            let signatures = Vec::<(AccountId, MultiSignature)>::decode(&mut &op.signature[..])
+                .unwrap_or_default();
+            let (spent, within_limit) = self.track_spending(&op);
+
+            let session_key = match signatures.as_slice() {
+                [(key, signature)] => self
+                    .session_keys
+                    .get((self.epoch, *key))
+                    .map(|session_key| (*key, signature.clone(), session_key)),
+                _ => None,
+            };
+            let validation_data = match session_key {
+                Some((key, signature, session_key)) => self.validate_session_key(
+                    &op,
+                    &user_op_hash,
+                    key,
+                    &signature,
+                    session_key,
+                    within_limit,
+                ),
+                None => self.validate_owners(signatures, &user_op_hash, within_limit),
+            };
+
+            if validation_data.aggregator == Aggregator::NoAggregator {
+                for (asset, spent) in spent {
+                    self.spent.insert(asset, &spent);
+                }
+                self.scheduled_ops.remove((self.epoch, user_op_hash));
+            }
+            Ok(validation_data)
         }
 
+        /// nonce 由 EntryPoint 的 NonceManager 校验,账户不做额外限制。
         #[ink(message)]
         fn validate_nonce(&self, _nonce: [u8; 32]) -> Result<()> {
             Ok(())
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink_aa::core::user_operation::UserOperationCall;
+        use scale::Encode;
+
+        fn signer(seed: u8) -> (ed25519_zebra::SigningKey, AccountId) {
+            let key = ed25519_zebra::SigningKey::from([seed; 32]);
+            let public = <[u8; 32]>::from(ed25519_zebra::VerificationKey::from(&key));
+            (key, AccountId::from(public))
+        }
+
+        fn sign(key: &ed25519_zebra::SigningKey, hash: &Hash) -> MultiSignature {
+            MultiSignature::Ed25519(key.sign(hash.as_ref()).into())
+        }
+
+        fn user_op(signature: Vec<u8>) -> UserOperation<AAEnvironment> {
+            UserOperation {
+                signature,
+                ..Default::default()
+            }
+        }
+
+        /// 创建钱包,绑定账户并以账户作为调用者。
+        fn wallet(requirement: u32, owners: Vec<AccountId>) -> RecoverSig {
+            let mut wallet = RecoverSig::new(requirement, owners);
+            let account = bind_account(&mut wallet);
+            set_caller(account);
+            wallet
+        }
+
+        fn validate(
+            wallet: &mut RecoverSig,
+            signatures: Vec<(AccountId, MultiSignature)>,
+            hash: Hash,
+        ) -> Aggregator<AAEnvironment> {
+            wallet
+                .validate_signature(user_op(signatures.encode()), hash)
+                .unwrap()
+                .aggregator
+        }
+
+        #[ink::test]
+        fn requirement_of_distinct_owners_is_enforced() {
+            let (alice_key, alice) = signer(1);
+            let (bob_key, bob) = signer(2);
+            let (_, charlie) = signer(3);
+            let mut wallet = wallet(2, vec![alice, bob, charlie]);
+            let hash = Hash::from([7; 32]);
+
+            assert_eq!(
+                validate(
+                    &mut wallet,
+                    vec![
+                        (alice, sign(&alice_key, &hash)),
+                        (bob, sign(&bob_key, &hash))
+                    ],
+                    hash
+                ),
+                Aggregator::NoAggregator
+            );
+            assert_eq!(
+                validate(&mut wallet, vec![(alice, sign(&alice_key, &hash))], hash),
+                Aggregator::IllegalAggregator
+            );
+            // 同一个所有者重复签名只计一次。
+            assert_eq!(
+                validate(
+                    &mut wallet,
+                    vec![
+                        (alice, sign(&alice_key, &hash)),
+                        (alice, sign(&alice_key, &hash))
+                    ],
+                    hash
+                ),
+                Aggregator::IllegalAggregator
+            );
+        }
+
+        #[ink::test]
+        fn invalid_signatures_are_rejected() {
+            let (alice_key, alice) = signer(1);
+            let (bob_key, bob) = signer(2);
+            let (eve_key, eve) = signer(5);
+            let mut wallet = wallet(1, vec![alice, bob]);
+            let hash = Hash::from([7; 32]);
+
+            // 非所有者的签名。
+            assert_eq!(
+                validate(&mut wallet, vec![(eve, sign(&eve_key, &hash))], hash),
+                Aggregator::IllegalAggregator
+            );
+            // 签名与声明的所有者不匹配。
+            assert_eq!(
+                validate(&mut wallet, vec![(alice, sign(&bob_key, &hash))], hash),
+                Aggregator::IllegalAggregator
+            );
+            // 对其他哈希的签名。
+            assert_eq!(
+                validate(
+                    &mut wallet,
+                    vec![(alice, sign(&alice_key, &Hash::from([8; 32])))],
+                    hash
+                ),
+                Aggregator::IllegalAggregator
+            );
+            // 无法解码的签名。
+            assert_eq!(
+                wallet
+                    .validate_signature(user_op(vec![1, 2, 3]), hash)
+                    .unwrap()
+                    .aggregator,
+                Aggregator::IllegalAggregator
+            );
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<AAEnvironment>(caller);
+        }
+
+        /// 绑定使用钱包的账户并返回它的地址,调用者需要是部署者。
+        fn bind_account(wallet: &mut RecoverSig) -> AccountId {
+            let account = AccountId::from([0xaa; 32]);
+            wallet.set_account(account);
+            account
+        }
+
+        #[ink::test]
+        fn account_manages_guardians() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+            let account = bind_account(&mut wallet);
+
+            set_caller(account);
+            wallet.add_guardian(accounts.eve);
+            wallet.remove_guardian(accounts.bob);
+            wallet.change_recovery_config(3, 10);
+            assert_eq!(
+                wallet.get_guardians(),
+                vec![accounts.eve, accounts.charlie, accounts.django]
+            );
+            assert_eq!((wallet.guardian_threshold, wallet.recovery_delay), (3, 10));
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn owner_cannot_change_guardians_directly() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+            bind_account(&mut wallet);
+
+            set_caller(accounts.alice);
+            wallet.add_guardian(accounts.eve);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn only_deployer_can_bind_account() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+
+            set_caller(accounts.bob);
+            wallet.set_account(accounts.bob);
+        }
+
+        fn wallet_with_guardians() -> RecoverSig {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            RecoverSig::with_guardians(
+                1,
+                vec![accounts.alice],
+                vec![accounts.bob, accounts.charlie, accounts.django],
+                2,
+                1000,
+            )
+        }
+
+        #[ink::test]
+        fn recovery_replaces_owners_after_delay() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+
+            set_caller(accounts.bob);
+            let id = wallet.initiate_recovery(vec![accounts.eve, accounts.frank], 2);
+            set_caller(accounts.charlie);
+            wallet.support_recovery(id);
+
+            ink::env::test::set_block_timestamp::<AAEnvironment>(1000);
+            wallet.execute_recovery(id);
+
+            assert_eq!(wallet.owners, vec![accounts.eve, accounts.frank]);
+            assert_eq!(wallet.requirement, 2);
+            assert!(!wallet.is_owner.contains(accounts.alice));
+            assert!(wallet.is_owner.contains(accounts.eve));
+            assert_eq!(wallet.get_recovery(), None);
+            assert_eq!(ink::env::test::recorded_events().count(), 3);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn recovery_cannot_execute_before_delay() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+
+            set_caller(accounts.bob);
+            let id = wallet.initiate_recovery(vec![accounts.eve], 1);
+            set_caller(accounts.charlie);
+            wallet.support_recovery(id);
+
+            ink::env::test::set_block_timestamp::<AAEnvironment>(999);
+            wallet.execute_recovery(id);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn recovery_needs_guardian_threshold() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+
+            set_caller(accounts.bob);
+            let id = wallet.initiate_recovery(vec![accounts.eve], 1);
+
+            ink::env::test::set_block_timestamp::<AAEnvironment>(1000);
+            wallet.execute_recovery(id);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn only_guardian_can_initiate_recovery() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+
+            set_caller(accounts.alice);
+            wallet.initiate_recovery(vec![accounts.eve], 1);
+        }
+
+        #[ink::test]
+        fn owner_can_cancel_recovery() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+
+            set_caller(accounts.bob);
+            let id = wallet.initiate_recovery(vec![accounts.eve], 1);
+            assert_eq!(wallet.get_recovery().map(|r| r.id), Some(id));
+
+            set_caller(accounts.alice);
+            wallet.cancel_recovery();
+            assert_eq!(wallet.get_recovery(), None);
+            assert_eq!(wallet.owners, vec![accounts.alice]);
+
+            // 取消之后可以发起新的恢复。
+            set_caller(accounts.charlie);
+            let id = wallet.initiate_recovery(vec![accounts.frank], 1);
+            assert_eq!(wallet.get_recovery().map(|r| r.id), Some(id));
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn guardian_cannot_replace_pending_recovery() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+
+            set_caller(accounts.bob);
+            wallet.initiate_recovery(vec![accounts.eve], 1);
+            set_caller(accounts.charlie);
+            wallet.initiate_recovery(vec![accounts.frank], 1);
+        }
+
+        #[ink::test]
+        fn recovery_without_guardian_supporters_can_be_replaced() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+            let account = bind_account(&mut wallet);
+
+            set_caller(accounts.bob);
+            wallet.initiate_recovery(vec![accounts.eve], 1);
+            set_caller(account);
+            wallet.remove_guardian(accounts.bob);
+
+            set_caller(accounts.charlie);
+            let id = wallet.initiate_recovery(vec![accounts.frank], 1);
+            assert_eq!(
+                wallet.get_recovery().map(|r| (r.id, r.new_owners)),
+                Some((id, vec![accounts.frank]))
+            );
+        }
+
+        #[ink::test]
+        fn recovery_clears_session_keys_and_scheduled_ops() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+            let account = bind_account(&mut wallet);
+            let hash = Hash::from([7; 32]);
+
+            set_caller(account);
+            wallet.register_session_key(accounts.frank, session_key(accounts.eve, 25));
+            wallet.set_over_limit_policy(0, 10);
+            wallet.schedule_over_limit_op(hash);
+
+            set_caller(accounts.bob);
+            let id = wallet.initiate_recovery(vec![accounts.eve], 1);
+            set_caller(accounts.charlie);
+            wallet.support_recovery(id);
+            ink::env::test::set_block_timestamp::<AAEnvironment>(1000);
+            wallet.execute_recovery(id);
+
+            assert_eq!(wallet.get_session_key(accounts.frank), None);
+            assert!(!wallet.scheduled_ops.contains((wallet.epoch, hash)));
+        }
+
+        fn session_key(callee: AccountId, spending_limit: Balance) -> SessionKey {
+            SessionKey {
+                permissions: vec![(callee, [1, 2, 3, 4])],
+                spending_limit,
+                valid_after: 100,
+                valid_until: 200,
+            }
+        }
+
+        fn session_op(callee: AccountId, selector: [u8; 4]) -> UserOperation<AAEnvironment> {
+            UserOperation {
+                call: UserOperationCall::Call {
+                    callee,
+                    selector,
+                    call_data: Vec::new(),
+                    value: 0,
+                },
+                call_gas_limit: 10,
+                max_fee_per_gas: 1,
+                ..Default::default()
+            }
+        }
+
+        fn validate_session(
+            wallet: &mut RecoverSig,
+            mut op: UserOperation<AAEnvironment>,
+            key: &ed25519_zebra::SigningKey,
+            hash: Hash,
+        ) -> ValidationData<AAEnvironment> {
+            let public =
+                AccountId::from(<[u8; 32]>::from(ed25519_zebra::VerificationKey::from(key)));
+            op.signature = vec![(public, sign(key, &hash))].encode();
+            wallet.validate_signature(op, hash).unwrap()
+        }
+
+        #[ink::test]
+        fn session_key_is_scoped() {
+            let (_, alice) = signer(1);
+            let (session, session_id) = signer(9);
+            let callee = AccountId::from([5; 32]);
+            let mut wallet = wallet(1, vec![alice]);
+            wallet.register_session_key(session_id, session_key(callee, 25));
+            let hash = Hash::from([7; 32]);
+
+            let data = validate_session(
+                &mut wallet,
+                session_op(callee, [1, 2, 3, 4]),
+                &session,
+                hash,
+            );
+            assert_eq!(data.aggregator, Aggregator::NoAggregator);
+            assert_eq!((data.valid_after, data.valid_until), (100, 200));
+            assert_eq!(
+                wallet.get_session_key(session_id).unwrap().spending_limit,
+                15
+            );
+
+            // 不允许的方法。
+            let data = validate_session(
+                &mut wallet,
+                session_op(callee, [0, 0, 0, 0]),
+                &session,
+                hash,
+            );
+            assert_eq!(data.aggregator, Aggregator::IllegalAggregator);
+            // 不允许的合约。
+            let data = validate_session(
+                &mut wallet,
+                session_op(AccountId::from([6; 32]), [1, 2, 3, 4]),
+                &session,
+                hash,
+            );
+            assert_eq!(data.aggregator, Aggregator::IllegalAggregator);
+
+            // 超过花费上限。
+            let data = validate_session(
+                &mut wallet,
+                session_op(callee, [1, 2, 3, 4]),
+                &session,
+                hash,
+            );
+            assert_eq!(data.aggregator, Aggregator::NoAggregator);
+            let data = validate_session(
+                &mut wallet,
+                session_op(callee, [1, 2, 3, 4]),
+                &session,
+                hash,
+            );
+            assert_eq!(data.aggregator, Aggregator::IllegalAggregator);
+            assert_eq!(
+                wallet.get_session_key(session_id).unwrap().spending_limit,
+                5
+            );
+        }
+
+        #[ink::test]
+        fn session_key_batch_must_be_fully_permitted() {
+            let (_, alice) = signer(1);
+            let (session, session_id) = signer(9);
+            let callee = AccountId::from([5; 32]);
+            let mut wallet = wallet(1, vec![alice]);
+            wallet.register_session_key(session_id, session_key(callee, 100));
+
+            let permitted = Transaction::new(callee, [1, 2, 3, 4], Vec::new(), 0, 0);
+            let forbidden = Transaction::new(callee, [0, 0, 0, 0], Vec::new(), 0, 0);
+            let op = UserOperation {
+                call: UserOperationCall::Batch(vec![permitted.clone(), forbidden]),
+                ..Default::default()
+            };
+            let data = validate_session(&mut wallet, op, &session, Hash::from([7; 32]));
+            assert_eq!(data.aggregator, Aggregator::IllegalAggregator);
+
+            let op = UserOperation {
+                call: UserOperationCall::Batch(vec![permitted.clone(), permitted]),
+                ..Default::default()
+            };
+            let data = validate_session(&mut wallet, op, &session, Hash::from([7; 32]));
+            assert_eq!(data.aggregator, Aggregator::NoAggregator);
+        }
+
+        #[ink::test]
+        fn revoked_session_key_is_rejected() {
+            let (_, alice) = signer(1);
+            let (session, session_id) = signer(9);
+            let callee = AccountId::from([5; 32]);
+            let mut wallet = wallet(1, vec![alice]);
+            wallet.register_session_key(session_id, session_key(callee, 100));
+            // 任何一个所有者都可以直接撤销会话密钥。
+            set_caller(alice);
+            wallet.revoke_session_key(session_id);
+            set_caller(wallet.get_account().unwrap());
+
+            let data = validate_session(
+                &mut wallet,
+                session_op(callee, [1, 2, 3, 4]),
+                &session,
+                Hash::from([7; 32]),
+            );
+            assert_eq!(data.aggregator, Aggregator::IllegalAggregator);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn owner_cannot_register_session_key_directly() {
+            let (_, alice) = signer(1);
+            let (_, session_id) = signer(9);
+            let mut wallet = wallet(1, vec![alice]);
+            set_caller(alice);
+            wallet.register_session_key(session_id, session_key(AccountId::from([5; 32]), 100));
+        }
+
+        #[ink::test]
+        fn only_account_can_validate() {
+            let (alice_key, alice) = signer(1);
+            let (session, session_id) = signer(9);
+            let callee = AccountId::from([5; 32]);
+            let mut wallet = wallet(1, vec![alice]);
+            wallet.register_session_key(session_id, session_key(callee, 25));
+            let hash = Hash::from([7; 32]);
+
+            set_caller(alice);
+            let signatures = vec![(alice, sign(&alice_key, &hash))];
+            assert!(matches!(
+                wallet.validate_signature(user_op(signatures.encode()), hash),
+                Err(Error::NotFromAccount)
+            ));
+            let mut op = session_op(callee, [1, 2, 3, 4]);
+            op.signature = vec![(session_id, sign(&session, &hash))].encode();
+            assert!(matches!(
+                wallet.validate_signature(op, hash),
+                Err(Error::NotFromAccount)
+            ));
+            // 会话密钥的花费上限没有被扣除。
+            assert_eq!(
+                wallet.get_session_key(session_id).unwrap().spending_limit,
+                25
+            );
+        }
+
+        const TOKEN: [u8; 32] = [4; 32];
+
+        fn token_transfer(value: Balance) -> Transaction<AAEnvironment> {
+            Transaction::new(
+                AccountId::from(TOKEN),
+                TRANSFER_SELECTOR,
+                (AccountId::from([5; 32]), value, Vec::<u8>::new()).encode(),
+                0,
+                0,
+            )
+        }
+
+        fn token_transfer_op(
+            value: Balance,
+            signers: &[&ed25519_zebra::SigningKey],
+            hash: Hash,
+        ) -> UserOperation<AAEnvironment> {
+            let transfer = token_transfer(value);
+            let call = UserOperationCall::Call {
+                callee: transfer.callee,
+                selector: transfer.selector,
+                call_data: transfer.input,
+                value: transfer.transferred_value,
+            };
+            signed_op(call, signers, hash)
+        }
+
+        fn signed_op(
+            call: UserOperationCall<AAEnvironment>,
+            signers: &[&ed25519_zebra::SigningKey],
+            hash: Hash,
+        ) -> UserOperation<AAEnvironment> {
+            let signatures: Vec<(AccountId, MultiSignature)> = signers
+                .iter()
+                .map(|key| {
+                    let public = <[u8; 32]>::from(ed25519_zebra::VerificationKey::from(*key));
+                    (AccountId::from(public), sign(key, &hash))
+                })
+                .collect();
+            UserOperation {
+                call,
+                signature: signatures.encode(),
+                ..Default::default()
+            }
+        }
+
+        /// 两个所有者,一个签名即可执行,代币每 1000 毫秒最多花费 100。
+        fn wallet_with_limit(
+            over_limit_requirement: u32,
+            over_limit_delay: Timestamp,
+        ) -> (
+            RecoverSig,
+            ed25519_zebra::SigningKey,
+            ed25519_zebra::SigningKey,
+        ) {
+            let (alice_key, alice) = signer(1);
+            let (bob_key, bob) = signer(2);
+            let mut wallet = RecoverSig::new(1, vec![alice, bob]);
+            let account = bind_account(&mut wallet);
+            set_caller(account);
+            wallet.set_spending_limit(
+                Asset::Token(AccountId::from(TOKEN)),
+                Some(SpendingLimit {
+                    limit: 100,
+                    period: 1000,
+                }),
+            );
+            wallet.set_over_limit_policy(over_limit_requirement, over_limit_delay);
+            (wallet, alice_key, bob_key)
+        }
+
+        #[test]
+        fn transaction_spending_counts_value_and_psp22_transfer() {
+            let token = AccountId::from(TOKEN);
+            let transaction = Transaction::<AAEnvironment>::new(
+                token,
+                TRANSFER_SELECTOR,
+                (AccountId::from([5; 32]), 30u128, Vec::<u8>::new()).encode(),
+                7,
+                0,
+            );
+            assert_eq!(
+                transaction_spending(&transaction),
+                vec![(Asset::Native, 7), (Asset::Token(token), 30)]
+            );
+        }
+
+        #[test]
+        fn allowance_counts_as_token_spending() {
+            let token = AccountId::from(TOKEN);
+            let spender = AccountId::from([5; 32]);
+            for selector in [APPROVE_SELECTOR, INCREASE_ALLOWANCE_SELECTOR] {
+                let transaction = Transaction::<AAEnvironment>::new(
+                    token,
+                    selector,
+                    (spender, 30u128).encode(),
+                    0,
+                    0,
+                );
+                assert_eq!(
+                    transaction_spending(&transaction),
+                    vec![(Asset::Token(token), 30)]
+                );
+            }
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn owner_cannot_change_spending_limit_directly() {
+            let (mut wallet, _, _) = wallet_with_limit(0, 0);
+            let (_, alice) = signer(1);
+            set_caller(alice);
+            wallet.set_spending_limit(Asset::Token(AccountId::from(TOKEN)), None);
+        }
+
+        #[test]
+        fn call_value_is_native_spending() {
+            let op = UserOperation {
+                call: UserOperationCall::Call {
+                    callee: AccountId::from([5; 32]),
+                    selector: [1, 2, 3, 4],
+                    call_data: Vec::new(),
+                    value: 40,
+                },
+                ..Default::default()
+            };
+            assert_eq!(op_spending(&op), vec![(Asset::Native, 40)]);
+        }
+
+        #[ink::test]
+        fn spending_over_limit_needs_more_signatures() {
+            let (mut wallet, alice_key, bob_key) = wallet_with_limit(2, 0);
+            let asset = Asset::Token(AccountId::from(TOKEN));
+            let hash = Hash::from([7; 32]);
+
+            let op = token_transfer_op(60, &[&alice_key], hash);
+            let data = wallet.validate_signature(op, hash).unwrap();
+            assert_eq!(data.aggregator, Aggregator::NoAggregator);
+            assert_eq!(wallet.get_spent(asset), 60);
+
+            let op = token_transfer_op(60, &[&alice_key], hash);
+            let data = wallet.validate_signature(op, hash).unwrap();
+            assert_eq!(data.aggregator, Aggregator::IllegalAggregator);
+            assert_eq!(wallet.get_spent(asset), 60);
+
+            let op = token_transfer_op(60, &[&alice_key, &bob_key], hash);
+            let data = wallet.validate_signature(op, hash).unwrap();
+            assert_eq!(data.aggregator, Aggregator::NoAggregator);
+            assert_eq!(wallet.get_spent(asset), 120);
+
+            // 下一个周期重新计算。
+            ink::env::test::set_block_timestamp::<AAEnvironment>(1000);
+            assert_eq!(wallet.get_spent(asset), 0);
+            let op = token_transfer_op(60, &[&alice_key], hash);
+            let data = wallet.validate_signature(op, hash).unwrap();
+            assert_eq!(data.aggregator, Aggregator::NoAggregator);
+        }
+
+        #[ink::test]
+        fn batch_spending_is_summed() {
+            let (mut wallet, alice_key, _) = wallet_with_limit(0, 0);
+            let hash = Hash::from([7; 32]);
+
+            let batch = UserOperationCall::Batch(vec![token_transfer(60), token_transfer(60)]);
+            let op = signed_op(batch, &[&alice_key], hash);
+            let data = wallet.validate_signature(op, hash).unwrap();
+            assert_eq!(data.aggregator, Aggregator::IllegalAggregator);
+
+            let batch = UserOperationCall::Batch(vec![token_transfer(60), token_transfer(40)]);
+            let op = signed_op(batch, &[&alice_key], hash);
+            let data = wallet.validate_signature(op, hash).unwrap();
+            assert_eq!(data.aggregator, Aggregator::NoAggregator);
+            assert_eq!(wallet.get_spent(Asset::Token(AccountId::from(TOKEN))), 100);
+        }
+
+        #[ink::test]
+        fn scheduled_op_over_limit_is_delayed() {
+            let (mut wallet, alice_key, _) = wallet_with_limit(0, 500);
+            let hash = Hash::from([7; 32]);
+
+            let op = token_transfer_op(150, &[&alice_key], hash);
+            let data = wallet.validate_signature(op, hash).unwrap();
+            assert_eq!(data.aggregator, Aggregator::IllegalAggregator);
+
+            ink::env::test::set_block_timestamp::<AAEnvironment>(10);
+            wallet.schedule_over_limit_op(hash);
+
+            let op = token_transfer_op(150, &[&alice_key], hash);
+            let data = wallet.validate_signature(op, hash).unwrap();
+            assert_eq!(data.aggregator, Aggregator::NoAggregator);
+            assert_eq!(data.valid_after, 510);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn owner_cannot_schedule_over_limit_op_directly() {
+            let (mut wallet, _, _) = wallet_with_limit(0, 500);
+            let (_, alice) = signer(1);
+
+            set_caller(alice);
+            wallet.schedule_over_limit_op(Hash::from([7; 32]));
+        }
+
+        #[ink::test]
+        fn raising_requirement_raises_over_limit_requirement() {
+            let (_, alice) = signer(1);
+            let (_, bob) = signer(2);
+            let (_, charlie) = signer(3);
+            let mut wallet = wallet(1, vec![alice, bob, charlie]);
+            wallet.set_over_limit_policy(2, 0);
+
+            wallet.change_requirement(3);
+            assert_eq!(wallet.over_limit_requirement, 3);
+            // 降低 `requirement` 不会降低 `over_limit_requirement`。
+            wallet.change_requirement(1);
+            assert_eq!(wallet.over_limit_requirement, 3);
+            wallet.remove_owner(charlie);
+            assert_eq!(wallet.over_limit_requirement, 2);
+        }
+
+        #[ink::test]
+        fn recovery_keeps_over_limit_requirement_above_requirement() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let mut wallet = wallet_with_guardians();
+            let account = bind_account(&mut wallet);
+            set_caller(account);
+            wallet.set_over_limit_policy(1, 0);
+
+            set_caller(accounts.bob);
+            let id = wallet.initiate_recovery(vec![accounts.eve, accounts.frank], 2);
+            set_caller(accounts.charlie);
+            wallet.support_recovery(id);
+            ink::env::test::set_block_timestamp::<AAEnvironment>(1000);
+            wallet.execute_recovery(id);
+
+            assert_eq!(wallet.requirement, 2);
+            assert_eq!(wallet.over_limit_requirement, 2);
+        }
+    }
 }
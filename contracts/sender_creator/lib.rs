@@ -1,11 +1,24 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
+pub use self::sender_creator::SenderCreatorRef;
+
 #[ink::contract(env = ink_aa::core::env::AAEnvironment)]
 mod sender_creator {
     use core::mem::size_of;
+    use ink::env::call::{
+        build_call,
+        utils::{Argument, ArgumentList, EmptyArgumentList},
+        ExecutionInput, Selector,
+    };
     use ink::prelude::vec::Vec;
-    use ink_aa::core::error::Result;
+    use ink_aa::core::{
+        env::AAEnvironment,
+        error::{Error, Result},
+        exec::OpaqueTypes,
+    };
     use ink_aa::traits::sender_creator::ISenderCreator;
+    use scale::Decode;
+
     #[ink(storage)]
     pub struct SenderCreator;
 
@@ -14,56 +27,90 @@ mod sender_creator {
         pub fn new() -> Self {
             Self {}
         }
+    }
 
+    impl ISenderCreator for SenderCreator {
+        /// `init_code` 由工厂地址、工厂方法的选择器以及参数组成,
+        /// 工厂方法必须返回创建的账户地址。
         #[ink(message)]
-        pub fn create_sender(&self, init_code: Vec<u8>) -> AccountId {
-            const LEN: usize = size_of::<AccountId>();
-            let factory = AccountId::try_from(&init_code[0..LEN]);
-            if factory.is_err() {
-                return AccountId::from([0; LEN]);
-            }
-            let factory = factory.unwrap();
-            let init_call_data = &init_code[LEN..];
-            let selector = ink::env::call::Selector::new([0u8; 4]); // Selector for the constructor function
-
-            let call = ink::env::call::build_call::<ink_aa::core::env::AAEnvironment>()
+        fn create_sender(&mut self, init_code: Vec<u8>) -> Result<AccountId> {
+            let (factory, input) = parse_init_code(&init_code)?;
+            let call = build_call::<AAEnvironment>()
                 .call(factory)
                 .gas_limit(self.env().gas_left())
-                .exec_input(ink::env::call::ExecutionInput::new(selector).push_arg(init_call_data))
+                .exec_input(input)
                 .returns::<AccountId>()
                 .try_invoke();
 
             match call {
-                Ok(Ok(result)) => result,
-                _ => AccountId::from([0; LEN]),
+                Ok(Ok(result)) => Ok(result),
+                _ => Err(Error::AccountNotDeployed),
             }
         }
     }
 
-    // impl ISenderCreator for SenderCreator {
-    //     #[ink(message)]
-    //     fn create_sender(&mut self, init_code: Vec<u8>) -> Result<AccountId> {
-    //         // 从 init_code 中取出 factory 地址
-    //         let factory = AccountId::from_slice(&init_code[0..32]);
+    type InitInput = ExecutionInput<ArgumentList<Argument<OpaqueTypes>, EmptyArgumentList>>;
+
+    /// 将 `init_code` 拆分为工厂地址和调用工厂方法的输入,
+    /// 参数按原样传递,不再重新编码。
+    fn parse_init_code(init_code: &[u8]) -> Result<(AccountId, InitInput)> {
+        const LEN: usize = size_of::<AccountId>();
+        if init_code.len() < LEN + 4 {
+            return Err(Error::AccountNotDeployed);
+        }
+        let factory =
+            AccountId::decode(&mut &init_code[..LEN]).map_err(|_| Error::AccountNotDeployed)?;
+        let mut selector = [0u8; 4];
+        selector.copy_from_slice(&init_code[LEN..LEN + 4]);
+        let init_call_data = init_code[LEN + 4..].to_vec();
+        Ok((
+            factory,
+            ExecutionInput::new(Selector::new(selector)).push_arg(OpaqueTypes(init_call_data)),
+        ))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use scale::Encode;
 
-    //         // 构造调用参数
-    //         let salt = [0x42; 32];
-    //         let endowment = 0;
-    //         let gas_limit = 0; // no limit
-    //         let exec_input = ink::env::call::ExecutionInput::new(construct_selector());
+        #[test]
+        fn short_init_code_is_rejected() {
+            assert!(matches!(
+                parse_init_code(&[]),
+                Err(Error::AccountNotDeployed)
+            ));
+            // 只有工厂地址,缺少选择器。
+            assert!(matches!(
+                parse_init_code(&[1; 32]),
+                Err(Error::AccountNotDeployed)
+            ));
+            assert!(matches!(
+                parse_init_code(&[1; 35]),
+                Err(Error::AccountNotDeployed)
+            ));
+        }
 
-    //         // 调用 CreateBuilder 实例化
-    //         let account_id = ink::env::call::build_create()
-    //             .gas_limit(gas_limit)
-    //             .endowment(endowment)
-    //             .code_hash(factory.code_hash())
-    //             .salt_bytes(salt)
-    //             .exec_input(exec_input)
-    //             .returns::<AccountId>()
-    //             .try_instantiate()
-    //             .map_err(|e| e.into())?;
+        #[test]
+        fn init_code_without_arguments() {
+            let (factory, input) = parse_init_code(&[1; 36]).unwrap();
+            assert_eq!(factory, AccountId::from([1; 32]));
+            assert_eq!(input.encode(), vec![1; 4]);
+        }
 
-    //         Ok(account_id)
-    //     }
-    // }
+        #[test]
+        fn arguments_are_passed_verbatim() {
+            let factory = AccountId::from([1; 32]);
+            let selector = ink::selector_bytes!("create_account");
+            let args = (1u32, vec![AccountId::from([2; 32])], [3u8; 32]).encode();
+            let mut init_code = factory.encode();
+            init_code.extend(selector);
+            init_code.extend(&args);
+
+            let (parsed, input) = parse_init_code(&init_code).unwrap();
+            assert_eq!(parsed, factory);
+            // 调用数据为选择器 ++ 参数,参数没有额外的长度前缀。
+            assert_eq!(input.encode(), [&selector[..], &args[..]].concat());
+        }
+    }
 }
@@ -1,6 +1,6 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-pub use simple_paymaster::SimplePaymasterRef;
+pub use self::simple_paymaster::SimplePaymasterRef;
 
 #[ink::contract]
 mod simple_paymaster {
@@ -0,0 +1,256 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::sr25519_aggregator::{Sr25519Aggregator, Sr25519AggregatorRef};
+
+/// sr25519 签名聚合器。
+///
+/// sr25519 签名不能相加,聚合签名为按操作顺序排列的各个签名,SCALE 编码为 `Vec<[u8; 64]>`。
+/// 验证时使用 schnorrkel 的批量验证,一次多标量乘法代替逐个验证签名。
+/// 每个用户操作签名的消息为 `keccak256((user_op_hash, aggregator))`,其中 `user_op_hash` 为
+/// EntryPoint 的 `get_user_op_hash`,包含 EntryPoint 地址和链标识,签名上下文为 `substrate`,
+/// 发送者的公钥通过 `IAggregatedAccount::get_public_key` 获取。
+#[ink::contract(env = ink_aa::core::env::AAEnvironment)]
+mod sr25519_aggregator {
+    use ink::prelude::vec::Vec;
+    use ink_aa::core::{
+        env::AAEnvironment,
+        error::{Error, Result},
+        helpers::keccak256,
+        user_operation::UserOperation,
+    };
+    use ink_aa::traits::{
+        account::IAggregatedAccount, aggregator::IAggregator, entry_point::AggregatedAccountRef,
+    };
+    use scale::{Decode, Encode};
+    use schnorrkel::{PublicKey, Signature};
+
+    /// sr25519 签名使用的签名上下文,与 Substrate 保持一致。
+    const SIGNING_CONTEXT: &[u8] = b"substrate";
+
+    #[ink(storage)]
+    pub struct Sr25519Aggregator {
+        /// 使用此聚合器的 EntryPoint。
+        entry_point: AccountId,
+        /// 与 EntryPoint 的 `get_chain_id` 一致的链标识。
+        chain_id: Hash,
+    }
+
+    impl Sr25519Aggregator {
+        #[ink(constructor)]
+        pub fn new(entry_point: AccountId, chain_id: Hash) -> Self {
+            Self {
+                entry_point,
+                chain_id,
+            }
+        }
+
+        /// 返回 `user_op` 的发送者需要签名的消息。
+        #[ink(message)]
+        pub fn get_user_op_hash(&self, user_op: UserOperation<AAEnvironment>) -> [u8; 32] {
+            self.user_op_message(&user_op)
+        }
+
+        /// 用户操作签名的消息。
+        fn user_op_message(&self, user_op: &UserOperation<AAEnvironment>) -> [u8; 32] {
+            let user_op_hash = user_op.user_op_hash(&self.entry_point, &self.chain_id);
+            keccak256(&(user_op_hash, self.env().account_id()).encode())
+        }
+
+        fn validation_failed(&self) -> Error {
+            Error::SignatureValidationFailed {
+                aggregator: self.env().account_id(),
+            }
+        }
+
+        /// 批量验证 `signatures[i]` 是否为 `user_ops[i]` 的发送者的签名。
+        fn verify_user_ops(
+            &self,
+            user_ops: &[UserOperation<AAEnvironment>],
+            signatures: &[[u8; 64]],
+        ) -> Result<()> {
+            if user_ops.len() != signatures.len() {
+                return Err(self.validation_failed());
+            }
+            let mut messages = Vec::with_capacity(user_ops.len());
+            let mut public_keys = Vec::with_capacity(user_ops.len());
+            for user_op in user_ops {
+                let account: AggregatedAccountRef<AAEnvironment> = user_op.sender.into();
+                let public_key: [u8; 32] = account
+                    .get_public_key()
+                    .try_into()
+                    .map_err(|_| self.validation_failed())?;
+                messages.push(self.user_op_message(user_op));
+                public_keys.push(public_key);
+            }
+            if verify_batch(&messages, &public_keys, signatures) {
+                Ok(())
+            } else {
+                Err(self.validation_failed())
+            }
+        }
+    }
+
+    impl IAggregator for Sr25519Aggregator {
+        /// `signature` 为 `aggregate_signatures` 返回的 SCALE 编码的 `Vec<[u8; 64]>`。
+        #[ink(message)]
+        fn validate_signatures(
+            &self,
+            user_ops: Vec<UserOperation<AAEnvironment>>,
+            signature: Vec<u8>,
+        ) -> Result<()> {
+            let signatures = Vec::<[u8; 64]>::decode(&mut &signature[..])
+                .map_err(|_| self.validation_failed())?;
+            self.verify_user_ops(&user_ops, &signatures)
+        }
+
+        /// `op.signature` 为单个操作的 64 字节签名,验证通过后返回空值,
+        /// 签名只在聚合之后的 `UserOpsPerAggregator::signature` 中提交。
+        #[ink(message)]
+        fn validate_user_op_signature(
+            &self,
+            user_op: UserOperation<AAEnvironment>,
+        ) -> Result<Vec<u8>> {
+            let signature: [u8; 64] = user_op
+                .signature
+                .as_slice()
+                .try_into()
+                .map_err(|_| self.validation_failed())?;
+            self.verify_user_ops(core::slice::from_ref(&user_op), &[signature])?;
+            Ok(Vec::new())
+        }
+
+        /// 按顺序打包每个 `op.signature`。
+        #[ink(message)]
+        fn aggregate_signatures(
+            &self,
+            user_ops: Vec<UserOperation<AAEnvironment>>,
+        ) -> Result<Vec<u8>> {
+            let signatures = user_ops
+                .iter()
+                .map(|user_op| <[u8; 64]>::try_from(user_op.signature.as_slice()))
+                .collect::<core::result::Result<Vec<_>, _>>()
+                .map_err(|_| self.validation_failed())?;
+            Ok(signatures.encode())
+        }
+    }
+
+    /// 批量验证签名。合约中没有随机数来源,使用 schnorrkel 基于 Fiat-Shamir 的确定性批量验证。
+    fn verify_batch(
+        messages: &[[u8; 32]],
+        public_keys: &[[u8; 32]],
+        signatures: &[[u8; 64]],
+    ) -> bool {
+        let (Ok(public_keys), Ok(signatures)) = (
+            public_keys
+                .iter()
+                .map(|public_key| PublicKey::from_bytes(public_key))
+                .collect::<core::result::Result<Vec<_>, _>>(),
+            signatures
+                .iter()
+                .map(|signature| Signature::from_bytes(signature))
+                .collect::<core::result::Result<Vec<_>, _>>(),
+        ) else {
+            return false;
+        };
+        let context = schnorrkel::signing_context(SIGNING_CONTEXT);
+        let transcripts = messages.iter().map(|message| context.bytes(message));
+        schnorrkel::verify_batch_deterministic(transcripts, &signatures, &public_keys, false)
+            .is_ok()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use schnorrkel::{ExpansionMode, Keypair, MiniSecretKey};
+
+        fn keypair(seed: u8) -> Keypair {
+            MiniSecretKey::from_bytes(&[seed; 32])
+                .unwrap()
+                .expand_to_keypair(ExpansionMode::Ed25519)
+        }
+
+        fn sign(keypair: &Keypair, message: &[u8; 32]) -> [u8; 64] {
+            keypair.sign_simple(SIGNING_CONTEXT, message).to_bytes()
+        }
+
+        #[test]
+        fn batch_verifies_every_signature() {
+            let keypairs: Vec<_> = (1..=3).map(keypair).collect();
+            let messages: Vec<_> = (1..=3).map(|i| [i; 32]).collect();
+            let public_keys: Vec<_> = keypairs.iter().map(|k| k.public.to_bytes()).collect();
+            let signatures: Vec<_> = keypairs
+                .iter()
+                .zip(&messages)
+                .map(|(keypair, message)| sign(keypair, message))
+                .collect();
+
+            assert!(verify_batch(&messages, &public_keys, &signatures));
+
+            // 一个签名对应的消息被修改。
+            let mut tampered = messages.clone();
+            tampered[1] = [9; 32];
+            assert!(!verify_batch(&tampered, &public_keys, &signatures));
+
+            // 签名顺序与公钥不对应。
+            let mut swapped = signatures.clone();
+            swapped.swap(0, 1);
+            assert!(!verify_batch(&messages, &public_keys, &swapped));
+        }
+
+        fn aggregator(chain_id: u8) -> Sr25519Aggregator {
+            Sr25519Aggregator::new(AccountId::from([1; 32]), Hash::from([chain_id; 32]))
+        }
+
+        #[ink::test]
+        fn user_op_message_is_bound_to_entry_point_hash() {
+            let op = UserOperation::default();
+            let message = aggregator(1).get_user_op_hash(op.clone());
+
+            let user_op_hash = op.user_op_hash(&AccountId::from([1; 32]), &Hash::from([1; 32]));
+            let aggregator_id = ink::env::account_id::<AAEnvironment>();
+            assert_eq!(message, keccak256(&(user_op_hash, aggregator_id).encode()));
+            // 另一条链上的相同操作需要不同的签名。
+            assert_ne!(aggregator(2).get_user_op_hash(op), message);
+        }
+
+        #[ink::test]
+        fn aggregate_signatures_packs_op_signatures() {
+            let aggregator = aggregator(1);
+            let signatures = [sign(&keypair(1), &[1; 32]), sign(&keypair(2), &[2; 32])];
+            let ops: Vec<_> = signatures
+                .iter()
+                .map(|signature| UserOperation {
+                    signature: signature.to_vec(),
+                    ..Default::default()
+                })
+                .collect();
+
+            assert_eq!(
+                aggregator.aggregate_signatures(ops),
+                Ok(signatures.to_vec().encode())
+            );
+            assert!(matches!(
+                aggregator.aggregate_signatures(vec![UserOperation::default()]),
+                Err(Error::SignatureValidationFailed { .. })
+            ));
+        }
+
+        #[ink::test]
+        fn malformed_aggregated_signature_is_rejected() {
+            let aggregator = aggregator(1);
+
+            assert!(matches!(
+                aggregator.validate_signatures(Vec::new(), vec![1, 2, 3]),
+                Err(Error::SignatureValidationFailed { .. })
+            ));
+            // 签名数量与操作数量不一致。
+            assert!(matches!(
+                aggregator.validate_signatures(
+                    vec![UserOperation::default()],
+                    Vec::<[u8; 64]>::new().encode()
+                ),
+                Err(Error::SignatureValidationFailed { .. })
+            ));
+        }
+    }
+}
@@ -1,6 +1,6 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-pub use stake_manager::StakeManagerRef;
+pub use self::stake_manager::StakeManagerRef;
 
 #[ink::contract(env = ink_aa::core::env::AAEnvironment)]
 mod stake_manager {
@@ -10,12 +10,14 @@ mod stake_manager {
             env::AAEnvironment,
             error::{Error, Result},
         },
-        traits::stake_manager::{DepositInfo, IStakeManager},
+        traits::stake_manager::DepositInfo,
     };
 
     #[ink(storage)]
     pub struct StakeManager {
         deposits: Mapping<AccountId, DepositInfo<AAEnvironment>>,
+        /// 实例化此合约的 EntryPoint,只有它可以代替其他账户扣除或增加存款。
+        owner: AccountId,
     }
 
     // TODO: 等`event2.0`合并发布之后，转移到`traits`中
@@ -76,20 +78,28 @@ mod stake_manager {
     }
 
     impl StakeManager {
-        /// Constructor that initializes the `bool` value to the given `init_value`.
+        /// 由 EntryPoint 实例化,调用者即为所有者。
         #[ink(constructor)]
         pub fn new() -> Self {
             Self {
                 deposits: Mapping::default(),
+                owner: Self::env().caller(),
             }
         }
 
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// 从 `required_address` 的存款中扣除预付款,只能由所有者调用。
         #[ink(message, payable)]
         pub fn required_prefund(
             &mut self,
             required_address: AccountId,
             required_amount: Balance,
         ) -> Result<()> {
+            self.inner_only_owner()?;
             let info = self.deposits.get(required_address).unwrap_or_default();
             if required_amount > info.deposit {
                 return Err(Error::WithdrawAmountTooLarge);
@@ -100,7 +110,7 @@ mod stake_manager {
                 .ok_or(Error::DepositOverflow)?;
 
             self.deposits
-                .insert(Self::env().caller(), &DepositInfo { deposit, ..info });
+                .insert(required_address, &DepositInfo { deposit, ..info });
             // self.env().emit_event(Withdrawn {
             //     account: self.env().caller(),
             //     withdraw_address,
@@ -113,45 +123,48 @@ mod stake_manager {
             Ok(())
         }
 
+        /// 增加 `account` 的存款,只能由所有者调用。
         #[ink(message)]
         pub fn increment_deposit(&mut self, account: AccountId, amount: Balance) -> Result<()> {
-            let mut info = self.get_deposit_info(account);
-            let new_amount = info
-                .deposit
-                .checked_add(amount)
-                .ok_or(Error::DepositOverflow)?;
+            self.inner_only_owner()?;
+            self.inner_increment_deposit(account, amount)
+        }
 
-            info.deposit = new_amount;
-            self.deposits.insert(account, &info);
+        /// 记录 `account` 存入的 `amount`,只能由所有者调用。
+        ///
+        /// 存入的金额由 EntryPoint 持有,此合约只记录存款。
+        #[ink(message)]
+        pub fn deposit_for(&mut self, account: AccountId, amount: Balance) -> Result<()> {
+            self.increment_deposit(account, amount)?;
+            self.env().emit_event(Deposited {
+                account,
+                total_deposit: self.balance_of(account),
+            });
             Ok(())
         }
-    }
 
-    impl IStakeManager for StakeManager {
         #[ink(message)]
-        fn get_deposit_info(&self, account: AccountId) -> DepositInfo<AAEnvironment> {
+        pub fn get_deposit_info(&self, account: AccountId) -> DepositInfo<AAEnvironment> {
             self.deposits.get(account).unwrap_or_default()
         }
 
         #[ink(message)]
-        fn balance_of(&self, account: AccountId) -> Balance {
+        pub fn balance_of(&self, account: AccountId) -> Balance {
             self.get_deposit_info(account).deposit
         }
 
-        #[ink(message, payable)]
-        fn deposit_to(&mut self, account: AccountId) -> Result<()> {
-            self.increment_deposit(account, Self::env().transferred_value())?;
+        /// 为 `account` 增加 `amount` 的抵押并设置取回延迟,只能由所有者调用。
+        ///
+        /// 任何待处理的取回操作将被取消。
+        #[ink(message)]
+        pub fn add_stake_for(
+            &mut self,
+            account: AccountId,
+            unstake_delay_sec: Timestamp,
+            amount: Balance,
+        ) -> Result<()> {
+            self.inner_only_owner()?;
             let info = self.get_deposit_info(account);
-            self.env().emit_event(Deposited {
-                account,
-                total_deposit: info.deposit,
-            });
-            Ok(())
-        }
-
-        #[ink(message, payable)]
-        fn add_stake(&mut self, unstake_delay_sec: Timestamp) -> Result<()> {
-            let info = self.deposits.get(Self::env().caller()).unwrap_or_default();
 
             if unstake_delay_sec <= 0 {
                 return Err(Error::MustSpecifyUnstakeDelay);
@@ -162,14 +175,14 @@ mod stake_manager {
             }
             let stake = info
                 .stake
-                .checked_add(Self::env().transferred_value())
+                .checked_add(amount)
                 .ok_or(Error::DepositOverflow)?;
 
             if stake <= 0 {
                 return Err(Error::NoStakeSpecified);
             }
             self.deposits.insert(
-                Self::env().caller(),
+                account,
                 &DepositInfo {
                     deposit: info.deposit,
                     staked: true,
@@ -178,17 +191,19 @@ mod stake_manager {
                     withdraw_time: 0,
                 },
             );
-            Self::env().emit_event(StakeLocked {
-                account: Self::env().caller(),
+            self.env().emit_event(StakeLocked {
+                account,
                 total_staked: stake,
                 unstake_delay_sec,
             });
             Ok(())
         }
 
+        /// 取消 `account` 的抵押,只能由所有者调用。
         #[ink(message)]
-        fn unlock_stake(&mut self) -> Result<()> {
-            let info = self.get_deposit_info(self.env().caller());
+        pub fn unlock_stake_for(&mut self, account: AccountId) -> Result<()> {
+            self.inner_only_owner()?;
+            let info = self.get_deposit_info(account);
 
             if info.unstake_delay_sec <= 0 {
                 return Err(Error::NotStaked);
@@ -196,29 +211,38 @@ mod stake_manager {
             if !info.staked {
                 return Err(Error::AlreadyUnstaking);
             }
-            let withdraw_time = Self::env()
+            let withdraw_time = self
+                .env()
                 .block_timestamp()
                 .checked_add(info.unstake_delay_sec)
                 .unwrap_or(Timestamp::MAX);
 
             self.deposits.insert(
-                Self::env().caller(),
+                account,
                 &DepositInfo {
                     staked: false,
                     withdraw_time,
                     ..info
                 },
             );
-            Self::env().emit_event(StakeUnlocked {
-                account: Self::env().caller(),
+            self.env().emit_event(StakeUnlocked {
+                account,
                 withdraw_time,
             });
             Ok(())
         }
 
-        #[ink(message, payable)]
-        fn withdraw_stake(&mut self, withdraw_address: AccountId) -> Result<()> {
-            let info = self.deposits.get(Self::env().caller()).unwrap_or_default();
+        /// 清空 `account` 已到期的抵押并返回其金额,只能由所有者调用。
+        ///
+        /// 抵押由 EntryPoint 持有,由它转给 `withdraw_address`。
+        #[ink(message)]
+        pub fn withdraw_stake_for(
+            &mut self,
+            account: AccountId,
+            withdraw_address: AccountId,
+        ) -> Result<Balance> {
+            self.inner_only_owner()?;
+            let info = self.get_deposit_info(account);
             let stake = info.stake;
 
             if stake <= 0 {
@@ -229,12 +253,12 @@ mod stake_manager {
                 return Err(Error::MustCallUnlockStakeFirst);
             }
 
-            if info.withdraw_time > Self::env().block_timestamp() {
+            if info.withdraw_time > self.env().block_timestamp() {
                 return Err(Error::StakeWithdrawalIsNotDue);
             }
 
             self.deposits.insert(
-                Self::env().caller(),
+                account,
                 &DepositInfo {
                     unstake_delay_sec: 0,
                     withdraw_time: 0,
@@ -242,25 +266,26 @@ mod stake_manager {
                     ..info
                 },
             );
-            Self::env().emit_event(StakeWithdrawn {
-                account: Self::env().caller(),
+            self.env().emit_event(StakeWithdrawn {
+                account,
                 withdraw_address,
                 amount: stake,
             });
-            self.env()
-                .transfer(withdraw_address, stake)
-                .map_err(|_| Error::FailedToWithdrawStake)?;
-
-            Ok(())
+            Ok(stake)
         }
 
-        #[ink(message, payable)]
-        fn withdraw_to(
+        /// 从 `account` 的存款中扣除 `withdraw_amount`,只能由所有者调用。
+        ///
+        /// 存款由 EntryPoint 持有,由它转给 `withdraw_address`。
+        #[ink(message)]
+        pub fn withdraw_for(
             &mut self,
+            account: AccountId,
             withdraw_address: AccountId,
             withdraw_amount: Balance,
         ) -> Result<()> {
-            let info = self.deposits.get(Self::env().caller()).unwrap_or_default();
+            self.inner_only_owner()?;
+            let info = self.get_deposit_info(account);
             if withdraw_amount > info.deposit {
                 return Err(Error::WithdrawAmountTooLarge);
             }
@@ -270,17 +295,124 @@ mod stake_manager {
                 .ok_or(Error::DepositOverflow)?;
 
             self.deposits
-                .insert(Self::env().caller(), &DepositInfo { deposit, ..info });
+                .insert(account, &DepositInfo { deposit, ..info });
             self.env().emit_event(Withdrawn {
-                account: self.env().caller(),
+                account,
                 withdraw_address,
                 amount: withdraw_amount,
             });
-            self.env()
-                .transfer(withdraw_address, withdraw_amount)
-                .map_err(|_| Error::FailedToWithdraw)?;
+            Ok(())
+        }
 
+        fn inner_only_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotFromOwner);
+            }
             Ok(())
         }
+
+        fn inner_increment_deposit(&mut self, account: AccountId, amount: Balance) -> Result<()> {
+            let mut info = self.get_deposit_info(account);
+            let new_amount = info
+                .deposit
+                .checked_add(amount)
+                .ok_or(Error::DepositOverflow)?;
+
+            info.deposit = new_amount;
+            self.deposits.insert(account, &info);
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<AAEnvironment>(caller);
+        }
+
+        #[ink::test]
+        fn only_owner_can_increment_deposit() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            set_caller(accounts.alice);
+            let mut stake_manager = StakeManager::new();
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                stake_manager.increment_deposit(accounts.bob, 100),
+                Err(Error::NotFromOwner)
+            );
+            assert_eq!(
+                stake_manager.required_prefund(accounts.alice, 0),
+                Err(Error::NotFromOwner)
+            );
+            assert_eq!(stake_manager.balance_of(accounts.bob), 0);
+            assert_eq!(
+                stake_manager.deposit_for(accounts.bob, 100),
+                Err(Error::NotFromOwner)
+            );
+            assert_eq!(
+                stake_manager.withdraw_for(accounts.bob, accounts.bob, 0),
+                Err(Error::NotFromOwner)
+            );
+            assert_eq!(
+                stake_manager.add_stake_for(accounts.bob, 1, 100),
+                Err(Error::NotFromOwner)
+            );
+            assert_eq!(
+                stake_manager.unlock_stake_for(accounts.bob),
+                Err(Error::NotFromOwner)
+            );
+            assert_eq!(
+                stake_manager.withdraw_stake_for(accounts.bob, accounts.bob),
+                Err(Error::NotFromOwner)
+            );
+        }
+
+        #[ink::test]
+        fn stake_is_withdrawn_after_delay() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            set_caller(accounts.alice);
+            let mut stake_manager = StakeManager::new();
+
+            assert_eq!(stake_manager.add_stake_for(accounts.bob, 10, 100), Ok(()));
+            assert_eq!(
+                stake_manager.withdraw_stake_for(accounts.bob, accounts.bob),
+                Err(Error::MustCallUnlockStakeFirst)
+            );
+            assert_eq!(stake_manager.unlock_stake_for(accounts.bob), Ok(()));
+            assert_eq!(
+                stake_manager.withdraw_stake_for(accounts.bob, accounts.bob),
+                Err(Error::StakeWithdrawalIsNotDue)
+            );
+            ink::env::test::set_block_timestamp::<AAEnvironment>(10);
+            assert_eq!(
+                stake_manager.withdraw_stake_for(accounts.bob, accounts.bob),
+                Ok(100)
+            );
+            assert_eq!(stake_manager.get_deposit_info(accounts.bob).stake, 0);
+        }
+
+        #[ink::test]
+        fn required_prefund_debits_required_address() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            set_caller(accounts.alice);
+            let mut stake_manager = StakeManager::new();
+
+            assert_eq!(stake_manager.increment_deposit(accounts.bob, 100), Ok(()));
+            assert_eq!(stake_manager.required_prefund(accounts.bob, 40), Ok(()));
+            assert_eq!(stake_manager.balance_of(accounts.bob), 60);
+            assert_eq!(stake_manager.balance_of(accounts.alice), 0);
+            assert_eq!(
+                stake_manager.required_prefund(accounts.bob, 61),
+                Err(Error::WithdrawAmountTooLarge)
+            );
+            assert_eq!(
+                stake_manager.withdraw_for(accounts.bob, accounts.eve, 60),
+                Ok(())
+            );
+            assert_eq!(stake_manager.balance_of(accounts.bob), 0);
+        }
     }
 }
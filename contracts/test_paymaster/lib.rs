@@ -0,0 +1,90 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::test_paymaster::TestPaymasterRef;
+
+/// 用于测试 EntryPoint 的 paymaster。
+///
+/// 返回非空的上下文使 EntryPoint 调用 `post_op`,`post_op` 只在 `PostOpMode::PostOpReverted`
+/// 模式下成功,即第一次调用总是失败,EntryPoint 需要回滚用户操作后再次调用。
+#[ink::contract]
+mod test_paymaster {
+    use base_paymaster::BasePaymasterTrait;
+    use ink::prelude::{vec, vec::Vec};
+    use ink_aa::core::{
+        env::AAEnvironment,
+        error::{Error, Result},
+        helpers::{Aggregator, ValidationData},
+        user_operation::UserOperation,
+    };
+    use ink_aa::traits::paymaster::PostOpMode;
+
+    #[ink(storage)]
+    pub struct TestPaymaster;
+
+    impl TestPaymaster {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {}
+        }
+    }
+
+    impl BasePaymasterTrait for TestPaymaster {
+        #[ink(message)]
+        fn validate_paymaster_user_op(
+            &self,
+            _user_op: UserOperation<AAEnvironment>,
+            _user_op_hash: Hash,
+            _max_cost: Balance,
+        ) -> Result<(Vec<u8>, ValidationData<AAEnvironment>)> {
+            Ok((
+                vec![1],
+                ValidationData {
+                    aggregator: Aggregator::NoAggregator,
+                    valid_after: 0,
+                    valid_until: Timestamp::MAX,
+                },
+            ))
+        }
+
+        #[ink(message)]
+        fn post_op(
+            &self,
+            mode: PostOpMode,
+            _context: Vec<u8>,
+            _actual_gas_cost: Balance,
+        ) -> Result<()> {
+            if mode == PostOpMode::PostOpReverted {
+                Ok(())
+            } else {
+                Err(Error::Revert)
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn post_op_only_succeeds_after_revert() {
+            let paymaster = TestPaymaster::new();
+            let (context, _) = paymaster
+                .validate_paymaster_user_op(UserOperation::default(), Hash::from([0; 32]), 0)
+                .unwrap();
+            assert!(!context.is_empty());
+
+            assert_eq!(
+                paymaster.post_op(PostOpMode::OpSucceeded, context.clone(), 0),
+                Err(Error::Revert)
+            );
+            assert_eq!(
+                paymaster.post_op(PostOpMode::OpReverted, context.clone(), 0),
+                Err(Error::Revert)
+            );
+            assert_eq!(
+                paymaster.post_op(PostOpMode::PostOpReverted, context, 0),
+                Ok(())
+            );
+        }
+    }
+}
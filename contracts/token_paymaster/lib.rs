@@ -0,0 +1,268 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::token_paymaster::{TokenPaymaster, TokenPaymasterRef, PRICE_DENOMINATOR};
+
+/// 使用 PSP22 代币支付 gas 的 paymaster。
+///
+/// 作为 `BasePaymaster` 的 `advance` 使用,由 `BasePaymaster` 负责质押和存款。
+/// 用户需要事先授权此合约转出代币;验证时按照最大费用从用户处转出代币,
+/// postOp 时按照汇率将实际的 gas 费用换算为代币,并退还多收的部分。
+#[ink::contract(env = ink_aa::core::env::AAEnvironment)]
+mod token_paymaster {
+    use base_paymaster::BasePaymasterTrait;
+    use ink::prelude::vec::Vec;
+    use ink_aa::core::{
+        env::AAEnvironment,
+        error::{Error, Result},
+        helpers::{Aggregator, ValidationData},
+        user_operation::UserOperation,
+    };
+    use ink_aa::traits::{
+        paymaster::PostOpMode,
+        psp22::{PSP22Error, PSP22},
+    };
+    use scale::{Decode, Encode};
+
+    /// 汇率的精度,`exchange_rate` 为每单位原生代币可兑换的代币数量乘以此值。
+    pub const PRICE_DENOMINATOR: Balance = 1_000_000;
+
+    #[ink(storage)]
+    pub struct TokenPaymaster {
+        /// 使用此合约的 `BasePaymaster`,只有它可以调用验证和 postOp。
+        base_paymaster: AccountId,
+        /// 用于支付费用的 PSP22 代币。
+        token: AccountId,
+        /// 每单位原生代币对应的代币数量,精度为 `PRICE_DENOMINATOR`。
+        exchange_rate: Balance,
+        /// 可以修改汇率和提取代币的所有者。
+        owner: AccountId,
+    }
+
+    impl TokenPaymaster {
+        /// 部署者即为所有者。
+        #[ink(constructor)]
+        pub fn new(base_paymaster: AccountId, token: AccountId, exchange_rate: Balance) -> Self {
+            Self {
+                base_paymaster,
+                token,
+                exchange_rate,
+                owner: Self::env().caller(),
+            }
+        }
+
+        #[ink(message)]
+        pub fn get_base_paymaster(&self) -> AccountId {
+            self.base_paymaster
+        }
+
+        #[ink(message)]
+        pub fn get_token(&self) -> AccountId {
+            self.token
+        }
+
+        #[ink(message)]
+        pub fn get_exchange_rate(&self) -> Balance {
+            self.exchange_rate
+        }
+
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// 修改汇率,只能由所有者调用。
+        #[ink(message)]
+        pub fn set_exchange_rate(&mut self, exchange_rate: Balance) -> Result<()> {
+            self.inner_only_owner()?;
+            self.exchange_rate = exchange_rate;
+            Ok(())
+        }
+
+        /// 将收取的代币转给 `to`,只能由所有者调用。
+        #[ink(message)]
+        pub fn withdraw_token(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.inner_only_owner()?;
+            self.token_ref()
+                .transfer(to, value, Vec::new())
+                .map_err(psp22_error)
+        }
+
+        /// 将原生代币数量按照汇率换算为代币数量,向上取整。
+        #[ink(message)]
+        pub fn token_cost(&self, native_cost: Balance) -> Result<Balance> {
+            native_cost
+                .checked_mul(self.exchange_rate)
+                .and_then(|cost| cost.checked_add(PRICE_DENOMINATOR - 1))
+                .map(|cost| cost / PRICE_DENOMINATOR)
+                .ok_or(Error::GasValuesOverflow)
+        }
+
+        fn inner_require_from_base_paymaster(&self) -> Result<()> {
+            if self.env().caller() != self.base_paymaster {
+                return Err(Error::NotFromEntryPoint);
+            }
+            Ok(())
+        }
+
+        fn inner_only_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+
+        fn token_ref(&self) -> ink::contract_ref!(PSP22) {
+            self.token.into()
+        }
+    }
+
+    fn psp22_error(error: PSP22Error) -> Error {
+        match error {
+            PSP22Error::InsufficientBalance => Error::InsufficientBalance,
+            PSP22Error::InsufficientAllowance => Error::InsufficientAllowance,
+            _ => Error::Revert,
+        }
+    }
+
+    impl BasePaymasterTrait for TokenPaymaster {
+        /// 验证时即按照最大费用转出代币,保证 postOp 不会因为用户的余额或授权不足而失败。
+        /// `context` 为 SCALE 编码的 `(sender, max_token_cost)`。
+        #[ink(message)]
+        fn validate_paymaster_user_op(
+            &self,
+            user_op: UserOperation<AAEnvironment>,
+            _user_op_hash: Hash,
+            max_cost: Balance,
+        ) -> Result<(Vec<u8>, ValidationData<AAEnvironment>)> {
+            self.inner_require_from_base_paymaster()?;
+            let max_token_cost = self.token_cost(max_cost)?;
+            self.token_ref()
+                .transfer_from(
+                    user_op.sender,
+                    self.env().account_id(),
+                    max_token_cost,
+                    Vec::new(),
+                )
+                .map_err(psp22_error)?;
+            Ok((
+                (user_op.sender, max_token_cost).encode(),
+                ValidationData {
+                    aggregator: Aggregator::NoAggregator,
+                    valid_after: 0,
+                    valid_until: Timestamp::MAX,
+                },
+            ))
+        }
+
+        /// 无论用户操作是否成功都需要收费,退还验证时多转出的代币。
+        /// 在 `PostOpMode::PostOpReverted` 的第二次调用中用户操作已被回滚,
+        /// 但 gas 已经消耗,仍然按照实际费用收取;退款只从此合约的余额中转出,不会失败。
+        #[ink(message)]
+        fn post_op(
+            &self,
+            _mode: PostOpMode,
+            context: Vec<u8>,
+            actual_gas_cost: Balance,
+        ) -> Result<()> {
+            self.inner_require_from_base_paymaster()?;
+            let (sender, max_token_cost) = <(AccountId, Balance)>::decode(&mut &context[..])
+                .map_err(|_| Error::InvalidPaymasterData)?;
+            let actual_token_cost = self.token_cost(actual_gas_cost)?.min(max_token_cost);
+            let refund = max_token_cost - actual_token_cost;
+            if refund == 0 {
+                return Ok(());
+            }
+            self.token_ref()
+                .transfer(sender, refund, Vec::new())
+                .map_err(psp22_error)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<AAEnvironment>(caller);
+        }
+
+        #[ink::test]
+        fn token_cost_rounds_up() {
+            let paymaster = TokenPaymaster::new(
+                AccountId::from([3; 32]),
+                AccountId::from([4; 32]),
+                2_500_000,
+            );
+
+            assert_eq!(paymaster.token_cost(0), Ok(0));
+            assert_eq!(paymaster.token_cost(2), Ok(5));
+            // 2.5 * 3 = 7.5,向上取整。
+            assert_eq!(paymaster.token_cost(3), Ok(8));
+            assert_eq!(
+                paymaster.token_cost(Balance::MAX),
+                Err(Error::GasValuesOverflow)
+            );
+        }
+
+        #[ink::test]
+        fn only_owner_can_set_exchange_rate() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            set_caller(accounts.alice);
+            let mut paymaster = TokenPaymaster::new(
+                AccountId::from([3; 32]),
+                AccountId::from([4; 32]),
+                PRICE_DENOMINATOR,
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(paymaster.set_exchange_rate(1), Err(Error::NotOwner));
+            assert_eq!(
+                paymaster.withdraw_token(accounts.bob, 1),
+                Err(Error::NotOwner)
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(paymaster.set_exchange_rate(2 * PRICE_DENOMINATOR), Ok(()));
+            assert_eq!(paymaster.token_cost(10), Ok(20));
+        }
+
+        #[ink::test]
+        fn malformed_context_is_an_error() {
+            let paymaster = TokenPaymaster::new(
+                AccountId::from([3; 32]),
+                AccountId::from([4; 32]),
+                PRICE_DENOMINATOR,
+            );
+
+            set_caller(AccountId::from([3; 32]));
+            assert_eq!(
+                paymaster.post_op(PostOpMode::OpSucceeded, vec![1, 2, 3], 10),
+                Err(Error::InvalidPaymasterData)
+            );
+        }
+
+        #[ink::test]
+        fn only_base_paymaster_can_call() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            let paymaster =
+                TokenPaymaster::new(accounts.alice, AccountId::from([4; 32]), PRICE_DENOMINATOR);
+
+            set_caller(accounts.eve);
+            let context = (accounts.eve, 100 as Balance).encode();
+            assert_eq!(
+                paymaster.post_op(PostOpMode::OpSucceeded, context, 0),
+                Err(Error::NotFromEntryPoint)
+            );
+            let user_op = UserOperation::<AAEnvironment> {
+                sender: accounts.eve,
+                ..Default::default()
+            };
+            assert_eq!(
+                paymaster
+                    .validate_paymaster_user_op(user_op, Hash::default(), 0)
+                    .map(|(context, _)| context),
+                Err(Error::NotFromEntryPoint)
+            );
+        }
+    }
+}
@@ -0,0 +1,285 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::verifying_paymaster::{VerifyingPaymaster, VerifyingPaymasterRef};
+
+/// 由链下签名者批准的 paymaster。
+///
+/// 作为 `BasePaymaster` 的 `advance` 使用。链下服务决定为哪些 UserOperation 支付 gas,
+/// 并对 UserOperation 和有效时间范围签名;签名放在
+/// `PaymasterAndData::PaymasterAndData { data }` 中,
+/// `data` 为 SCALE 编码的 `(valid_until, valid_after, signature)`。
+#[ink::contract(env = ink_aa::core::env::AAEnvironment)]
+mod verifying_paymaster {
+    use base_paymaster::BasePaymasterTrait;
+    use ink::prelude::vec::Vec;
+    use ink_aa::core::{
+        env::AAEnvironment,
+        error::{Error, Result},
+        helpers::{keccak256, Aggregator, ValidationData},
+        signature::MultiSignature,
+        user_operation::{PaymasterAndData, UserOperation},
+    };
+    use ink_aa::traits::paymaster::PostOpMode;
+    use scale::{Decode, Encode};
+
+    #[ink(storage)]
+    pub struct VerifyingPaymaster {
+        /// 链下签名者。
+        signer: AccountId,
+        /// 链 id,与 EntryPoint 的链 id 相同,防止批准在其他链上被重放。
+        chain_id: Hash,
+        /// 可以更换签名者的所有者。
+        owner: AccountId,
+    }
+
+    impl VerifyingPaymaster {
+        /// 部署者即为所有者。
+        #[ink(constructor)]
+        pub fn new(signer: AccountId, chain_id: Hash) -> Self {
+            Self {
+                signer,
+                chain_id,
+                owner: Self::env().caller(),
+            }
+        }
+
+        #[ink(message)]
+        pub fn get_signer(&self) -> AccountId {
+            self.signer
+        }
+
+        #[ink(message)]
+        pub fn get_chain_id(&self) -> Hash {
+            self.chain_id
+        }
+
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// 更换链下签名者,只能由所有者调用。
+        #[ink(message)]
+        pub fn set_signer(&mut self, signer: AccountId) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            self.signer = signer;
+            Ok(())
+        }
+
+        /// 返回链下签名者需要签名的哈希。
+        ///
+        /// `paymaster_and_data` 中包含签名本身,因此先将它替换为 `OnlyPaymaster`
+        /// 再计算 UserOperation 的哈希,然后与此合约的地址、链 id 和有效时间范围一起计算 keccak256。
+        #[ink(message)]
+        pub fn get_hash(
+            &self,
+            user_op: UserOperation<AAEnvironment>,
+            valid_until: Timestamp,
+            valid_after: Timestamp,
+        ) -> [u8; 32] {
+            let paymaster = user_op.paymaster_and_data.paymaster();
+            let user_op = UserOperation {
+                paymaster_and_data: PaymasterAndData::OnlyPaymaster(paymaster),
+                ..user_op
+            };
+            keccak256(
+                &(
+                    user_op.hash(),
+                    self.env().account_id(),
+                    self.chain_id,
+                    valid_until,
+                    valid_after,
+                )
+                    .encode(),
+            )
+        }
+    }
+
+    impl BasePaymasterTrait for VerifyingPaymaster {
+        /// 签名无效时返回 `Aggregator::IllegalAggregator` 而不是回滚,
+        /// 数据无法解析时返回 `Error::InvalidPaymasterData`。
+        #[ink(message)]
+        fn validate_paymaster_user_op(
+            &self,
+            user_op: UserOperation<AAEnvironment>,
+            _user_op_hash: Hash,
+            _max_cost: Balance,
+        ) -> Result<(Vec<u8>, ValidationData<AAEnvironment>)> {
+            let PaymasterAndData::PaymasterAndData { data, .. } = &user_op.paymaster_and_data
+            else {
+                return Err(Error::InvalidPaymasterData);
+            };
+            let (valid_until, valid_after, signature) =
+                <(Timestamp, Timestamp, MultiSignature)>::decode(&mut &data[..])
+                    .map_err(|_| Error::InvalidPaymasterData)?;
+
+            let hash = self.get_hash(user_op.clone(), valid_until, valid_after);
+            let aggregator = if signature.verify(&hash, &self.signer) {
+                Aggregator::NoAggregator
+            } else {
+                Aggregator::IllegalAggregator
+            };
+            Ok((
+                Vec::new(),
+                ValidationData {
+                    aggregator,
+                    valid_after,
+                    valid_until,
+                },
+            ))
+        }
+
+        /// 不需要 postOp,`context` 总是为空。
+        #[ink(message)]
+        fn post_op(
+            &self,
+            _mode: PostOpMode,
+            _context: Vec<u8>,
+            _actual_gas_cost: Balance,
+        ) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const VALID_UNTIL: Timestamp = 2000;
+        const VALID_AFTER: Timestamp = 1000;
+        const CHAIN_ID: [u8; 32] = [1; 32];
+
+        fn signer(seed: u8) -> (ed25519_zebra::SigningKey, AccountId) {
+            let key = ed25519_zebra::SigningKey::from([seed; 32]);
+            let public = <[u8; 32]>::from(ed25519_zebra::VerificationKey::from(&key));
+            (key, AccountId::from(public))
+        }
+
+        fn with_data(data: Vec<u8>) -> UserOperation<AAEnvironment> {
+            UserOperation {
+                paymaster_and_data: PaymasterAndData::PaymasterAndData {
+                    paymaster: AccountId::from([3; 32]),
+                    data,
+                },
+                ..Default::default()
+            }
+        }
+
+        /// 返回由 `key` 批准、有效时间范围为 `valid_until`/`valid_after` 的 UserOperation。
+        fn sponsored_op(
+            paymaster: &VerifyingPaymaster,
+            key: &ed25519_zebra::SigningKey,
+            valid_until: Timestamp,
+            valid_after: Timestamp,
+        ) -> UserOperation<AAEnvironment> {
+            let hash = paymaster.get_hash(with_data(Vec::new()), valid_until, valid_after);
+            let signature = MultiSignature::Ed25519(key.sign(&hash).into());
+            with_data((valid_until, valid_after, signature).encode())
+        }
+
+        fn validate(
+            paymaster: &VerifyingPaymaster,
+            op: UserOperation<AAEnvironment>,
+        ) -> Result<ValidationData<AAEnvironment>> {
+            paymaster
+                .validate_paymaster_user_op(op, Hash::from([7; 32]), 0)
+                .map(|(_, data)| data)
+        }
+
+        #[ink::test]
+        fn signed_op_is_sponsored() {
+            let (key, signer) = signer(1);
+            let paymaster = VerifyingPaymaster::new(signer, Hash::from(CHAIN_ID));
+
+            let data = validate(
+                &paymaster,
+                sponsored_op(&paymaster, &key, VALID_UNTIL, VALID_AFTER),
+            )
+            .unwrap();
+            assert_eq!(data.aggregator, Aggregator::NoAggregator);
+            assert_eq!(data.valid_until, VALID_UNTIL);
+            assert_eq!(data.valid_after, VALID_AFTER);
+        }
+
+        #[ink::test]
+        fn invalid_approvals_are_rejected() {
+            let (key, signer_id) = signer(1);
+            let (other_key, _) = signer(2);
+            let paymaster = VerifyingPaymaster::new(signer_id, Hash::from(CHAIN_ID));
+
+            // 其他签名者。
+            let op = sponsored_op(&paymaster, &other_key, VALID_UNTIL, VALID_AFTER);
+            assert_eq!(
+                validate(&paymaster, op).unwrap().aggregator,
+                Aggregator::IllegalAggregator
+            );
+
+            // 签名之后修改了有效时间范围。
+            let op = sponsored_op(&paymaster, &key, VALID_UNTIL, VALID_AFTER);
+            let PaymasterAndData::PaymasterAndData { data, .. } = &op.paymaster_and_data else {
+                unreachable!()
+            };
+            let (_, valid_after, signature) =
+                <(Timestamp, Timestamp, MultiSignature)>::decode(&mut &data[..]).unwrap();
+            let op = with_data((Timestamp::MAX, valid_after, signature).encode());
+            assert_eq!(
+                validate(&paymaster, op).unwrap().aggregator,
+                Aggregator::IllegalAggregator
+            );
+
+            // 签名之后修改了 UserOperation。
+            let op = UserOperation {
+                call_gas_limit: 1,
+                ..sponsored_op(&paymaster, &key, VALID_UNTIL, VALID_AFTER)
+            };
+            assert_eq!(
+                validate(&paymaster, op).unwrap().aggregator,
+                Aggregator::IllegalAggregator
+            );
+        }
+
+        #[ink::test]
+        fn approval_is_bound_to_chain() {
+            let (key, signer) = signer(1);
+            let paymaster = VerifyingPaymaster::new(signer, Hash::from(CHAIN_ID));
+            let other_chain = VerifyingPaymaster::new(signer, Hash::from([2; 32]));
+
+            let op = sponsored_op(&paymaster, &key, VALID_UNTIL, VALID_AFTER);
+            assert_eq!(
+                validate(&other_chain, op).unwrap().aggregator,
+                Aggregator::IllegalAggregator
+            );
+        }
+
+        #[ink::test]
+        fn malformed_data_is_an_error() {
+            let (_, signer) = signer(1);
+            let paymaster = VerifyingPaymaster::new(signer, Hash::from(CHAIN_ID));
+
+            assert!(matches!(
+                validate(&paymaster, with_data(vec![1, 2, 3])),
+                Err(Error::InvalidPaymasterData)
+            ));
+            assert!(matches!(
+                validate(&paymaster, UserOperation::default()),
+                Err(Error::InvalidPaymasterData)
+            ));
+        }
+
+        #[ink::test]
+        fn only_owner_can_set_signer() {
+            let accounts = ink::env::test::default_accounts::<AAEnvironment>();
+            ink::env::test::set_caller::<AAEnvironment>(accounts.alice);
+            let mut paymaster = VerifyingPaymaster::new(accounts.bob, Hash::from(CHAIN_ID));
+
+            ink::env::test::set_caller::<AAEnvironment>(accounts.bob);
+            assert_eq!(paymaster.set_signer(accounts.bob), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<AAEnvironment>(accounts.alice);
+            assert_eq!(paymaster.set_signer(accounts.eve), Ok(()));
+            assert_eq!(paymaster.get_signer(), accounts.eve);
+        }
+    }
+}
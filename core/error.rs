@@ -3,7 +3,7 @@ use crate::traits::{
     stake_manager::StakeInfo,
 };
 use ink::env::Environment;
-use ink::prelude::string::String;
+use ink::prelude::boxed::Box;
 use ink::prelude::vec::Vec;
 
 use super::env::AAEnvironment;
@@ -20,11 +20,15 @@ where
     InvalidAggregator,
     NotOwner,
     NotFromEntryPoint,
+    /// 调用者不是合约的所有者(部署它的 EntryPoint)。
+    NotFromOwner,
     PaymasterDepositTooLow,
     PaymasterNotFound,
     TooLittleVerificationGas,
     InvalidAccountNonce,
     InvalidPaymasterAddress,
+    /// `paymaster_and_data` 中的数据无法被 paymaster 解析。
+    InvalidPaymasterData,
     OverVerificationGasLimit,
     GasValuesOverflow,
     FailedSendToBeneficiary,
@@ -48,22 +52,23 @@ where
     InsufficientAllowance,
     OnlyInternalCall,
     OutOfGas,
+    /// 账户执行的第 `index` 笔交易失败(单个调用时为 0),整批交易被回滚。
+    ExecutionFailed {
+        index: u32,
+    },
+    /// 调用者不是使用此验证器的账户。
+    NotFromAccount,
     /// handleOps 调用失败产生的错误,用于识别失败的操作。
     ///  若 simulateValidation 成功通过,则 handleOps 不存在失败的可能。
     ///   
     /// - `op_index` - 失败操作在数组中的索引(在 simulateValidation 中总是为 0)
-    /// - `reason` - 失败原因
-    ///   字符串以 "AAmn" 开头,其中 "m" 代表分类:  
-    ///      1 - fabric 失败
-    ///      2 - account 失败
-    ///      3 - paymaster 失败
-    ///    这样则可以归类到正确的实体。
-    ///    
+    /// - `reason` - 失败原因,见 [`FailedOpReason`]
+    ///
     /// 应该在宕机下的 handleOps 模拟中捕获,不应该在链上产生。
     /// 有助于防范批处理器或 paymaster/factory/account回滚攻击,或用于故障排除。
     FailedOp {
         op_index: u64,
-        reason: String,
+        reason: FailedOpReason<E>,
     },
     /// 签名聚合器无法验证它生成的聚合签名时的错误情况。     
     SignatureValidationFailed {
@@ -115,4 +120,201 @@ where
     },
 }
 
+/// `Error::FailedOp` 的失败原因。
+///
+/// 每个原因对应一个 "AAmn" 错误码,其中 "m" 代表分类:
+/// - 1 - factory 失败
+/// - 2 - account 失败
+/// - 3 - paymaster 失败
+/// - 4 - 验证阶段的 gas 不足
+/// - 5 - postOp 失败
+///
+/// 这样则可以归类到正确的实体。
+#[derive(PartialEq, Eq, scale::Encode, scale::Decode)]
+#[codec(encode_bound())]
+#[codec(decode_bound())]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo),
+    scale_info(bounds(
+        E: scale_info::TypeInfo + 'static,
+        E::AccountId: scale_info::TypeInfo + 'static,
+        E::Timestamp: scale_info::TypeInfo + 'static,
+        E::Balance: scale_info::TypeInfo + 'static,
+    ))
+)]
+pub enum FailedOpReason<E: Environment = AAEnvironment>
+where
+    E::Balance: core::fmt::Debug,
+{
+    /// AA10 发送者已经被创建,但仍然提供了 `init_code`。
+    SenderAlreadyConstructed,
+    /// AA13 `init_code` 执行失败或 gas 不足。
+    InitCodeFailed,
+    /// AA14 `init_code` 返回的地址与发送者不一致。
+    InitCodeMustReturnSender,
+    /// AA15 `init_code` 没有在发送者地址上创建合约。
+    InitCodeMustCreateSender,
+    /// AA21 账户没有支付足够的预付款。
+    DidNotPayPrefund,
+    /// AA22 账户的签名已过期或尚未生效。
+    ExpiredOrNotDue,
+    /// AA23 账户的 `validate_user_op` 返回了错误。
+    AccountReverted(Box<Error<E>>),
+    /// AA24 账户返回的签名聚合器与预期不一致。
+    SignatureError,
+    /// AA25 nonce 无效。
+    InvalidNonce,
+    /// AA31 paymaster 的存款不足。
+    PaymasterDepositTooLow,
+    /// AA32 paymaster 的签名已过期或尚未生效。
+    PaymasterExpiredOrNotDue,
+    /// AA33 paymaster 的 `validate_paymaster_user_op` 返回了错误。
+    PaymasterReverted(Box<Error<E>>),
+    /// AA34 paymaster 的签名验证失败。
+    PaymasterSignatureError,
+    /// AA40 验证阶段使用的 gas 超过 `verification_gas_limit`。
+    OverVerificationGasLimit,
+    /// AA41 `verification_gas_limit` 不足以调用 paymaster。
+    TooLittleVerificationGas,
+    /// AA50 paymaster 的 `post_op` 返回了错误。
+    PostOpReverted(Box<Error<E>>),
+    /// AA51 预付款少于实际的 gas 费用。
+    PrefundBelowActualGasCost { actual_gas_cost: u64 },
+    /// 无法归类到某个实体的错误,错误码为 0。
+    Other(Box<Error<E>>),
+}
+
+impl<E: Environment> FailedOpReason<E>
+where
+    E::Balance: core::fmt::Debug,
+{
+    /// 返回 "AAmn" 中的数字 `mn`,`code() / 10` 即为分类。
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::SenderAlreadyConstructed => 10,
+            Self::InitCodeFailed => 13,
+            Self::InitCodeMustReturnSender => 14,
+            Self::InitCodeMustCreateSender => 15,
+            Self::DidNotPayPrefund => 21,
+            Self::ExpiredOrNotDue => 22,
+            Self::AccountReverted(_) => 23,
+            Self::SignatureError => 24,
+            Self::InvalidNonce => 25,
+            Self::PaymasterDepositTooLow => 31,
+            Self::PaymasterExpiredOrNotDue => 32,
+            Self::PaymasterReverted(_) => 33,
+            Self::PaymasterSignatureError => 34,
+            Self::OverVerificationGasLimit => 40,
+            Self::TooLittleVerificationGas => 41,
+            Self::PostOpReverted(_) => 50,
+            Self::PrefundBelowActualGasCost { .. } => 51,
+            Self::Other(_) => 0,
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::fmt::Debug for FailedOpReason<E>
+where
+    E: Environment,
+    E::AccountId: core::fmt::Debug,
+    E::Timestamp: core::fmt::Debug,
+    E::Balance: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::SenderAlreadyConstructed => f.write_str("SenderAlreadyConstructed"),
+            Self::InitCodeFailed => f.write_str("InitCodeFailed"),
+            Self::InitCodeMustReturnSender => f.write_str("InitCodeMustReturnSender"),
+            Self::InitCodeMustCreateSender => f.write_str("InitCodeMustCreateSender"),
+            Self::DidNotPayPrefund => f.write_str("DidNotPayPrefund"),
+            Self::ExpiredOrNotDue => f.write_str("ExpiredOrNotDue"),
+            Self::AccountReverted(e) => f.debug_tuple("AccountReverted").field(e).finish(),
+            Self::SignatureError => f.write_str("SignatureError"),
+            Self::InvalidNonce => f.write_str("InvalidNonce"),
+            Self::PaymasterDepositTooLow => f.write_str("PaymasterDepositTooLow"),
+            Self::PaymasterExpiredOrNotDue => f.write_str("PaymasterExpiredOrNotDue"),
+            Self::PaymasterReverted(e) => f.debug_tuple("PaymasterReverted").field(e).finish(),
+            Self::PaymasterSignatureError => f.write_str("PaymasterSignatureError"),
+            Self::OverVerificationGasLimit => f.write_str("OverVerificationGasLimit"),
+            Self::TooLittleVerificationGas => f.write_str("TooLittleVerificationGas"),
+            Self::PostOpReverted(e) => f.debug_tuple("PostOpReverted").field(e).finish(),
+            Self::PrefundBelowActualGasCost { actual_gas_cost } => f
+                .debug_struct("PrefundBelowActualGasCost")
+                .field("actual_gas_cost", actual_gas_cost)
+                .finish(),
+            Self::Other(e) => f.debug_tuple("Other").field(e).finish(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Environment> core::fmt::Display for FailedOpReason<E>
+where
+    E::Balance: core::fmt::Debug,
+    Error<E>: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if let Self::Other(e) = self {
+            return write!(f, "{e:?}");
+        }
+        write!(f, "AA{} ", self.code())?;
+        match self {
+            Self::SenderAlreadyConstructed => write!(f, "sender already constructed"),
+            Self::InitCodeFailed => write!(f, "initCode failed or OOG"),
+            Self::InitCodeMustReturnSender => write!(f, "initCode must return sender"),
+            Self::InitCodeMustCreateSender => write!(f, "initCode must create sender"),
+            Self::DidNotPayPrefund => write!(f, "didn't pay prefund"),
+            Self::ExpiredOrNotDue => write!(f, "expired or not due"),
+            Self::AccountReverted(e) => write!(f, "reverted: {e:?}"),
+            Self::SignatureError | Self::PaymasterSignatureError => write!(f, "signature error"),
+            Self::InvalidNonce => write!(f, "invalid account nonce"),
+            Self::PaymasterDepositTooLow => write!(f, "paymaster deposit too low"),
+            Self::PaymasterExpiredOrNotDue => write!(f, "paymaster expired or not due"),
+            Self::PaymasterReverted(e) => write!(f, "reverted: {e:?}"),
+            Self::OverVerificationGasLimit => write!(f, "over verificationGasLimit"),
+            Self::TooLittleVerificationGas => write!(f, "too little verificationGas"),
+            Self::PostOpReverted(e) => write!(f, "postOp reverted: {e:?}"),
+            Self::PrefundBelowActualGasCost { actual_gas_cost } => {
+                write!(f, "prefund below {actual_gas_cost}")
+            }
+            Self::Other(_) => Ok(()),
+        }
+    }
+}
+
 pub type Result<T> = core::result::Result<T, Error<AAEnvironment>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale::{Decode, Encode};
+
+    #[test]
+    fn failed_op_reason_code_and_display() {
+        let reason: FailedOpReason = FailedOpReason::AccountReverted(Box::new(Error::NotOwner));
+        assert_eq!(reason.code(), 23);
+        assert_eq!(reason.to_string(), "AA23 reverted: NotOwner");
+
+        let reason: FailedOpReason =
+            FailedOpReason::PrefundBelowActualGasCost { actual_gas_cost: 7 };
+        assert_eq!(reason.code() / 10, 5);
+        assert_eq!(reason.to_string(), "AA51 prefund below 7");
+
+        let reason: FailedOpReason = FailedOpReason::Other(Box::new(Error::Revert));
+        assert_eq!(reason.code(), 0);
+        assert_eq!(reason.to_string(), "Revert");
+    }
+
+    #[test]
+    fn failed_op_round_trips() {
+        let error: Error = Error::FailedOp {
+            op_index: 3,
+            reason: FailedOpReason::PostOpReverted(Box::new(Error::FailedOp {
+                op_index: 3,
+                reason: FailedOpReason::InvalidNonce,
+            })),
+        };
+        assert_eq!(Error::decode(&mut &error.encode()[..]), Ok(error));
+    }
+}
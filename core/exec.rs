@@ -10,11 +10,10 @@ use ink::env::{
 use ink::prelude::vec;
 use ink::prelude::vec::Vec;
 
-#[derive(scale::Decode, scale::Encode, Clone, Hash)]
+#[derive(scale::Decode, scale::Encode, Clone, Hash, Debug)]
 #[cfg_attr(
     feature = "std",
     derive(
-        Debug,
         PartialEq,
         Eq,
         scale_info::TypeInfo,
@@ -46,16 +45,15 @@ impl<E: Environment> Transaction<E> {
         callee: E::AccountId,
         selector: [u8; 4],
         call_data: Vec<u8>,
+        transferred_value: E::Balance,
         gas_limit: u64,
     ) -> Self {
-        use num_traits::identities::Zero;
         Self {
             callee,
             selector,
             input: call_data,
             gas_limit,
-            // TODO:
-            transferred_value: E::Balance::zero(),
+            transferred_value,
             allow_reentry: false,
         }
     }
@@ -69,6 +67,92 @@ impl<E: Environment> Transaction<E> {
             .returns::<OpaqueTypes>()
             .params()
     }
+
+    /// 执行交易,成功时返回被调用消息的返回值,失败时返回 `None`。
+    pub fn invoke(self) -> Option<Vec<u8>> {
+        call_output(self.try_invoke())
+    }
+
+    /// 执行交易,被调用合约回滚时返回 `Error::CalleeReverted`。
+    ///
+    /// ink! 4 的 `try_invoke` 在被调用合约回滚时仍然解码其输出并返回 `Ok`,
+    /// 所以链上直接调用 `seal_call`,根据返回码判断是否回滚。
+    #[cfg(not(feature = "std"))]
+    pub fn try_invoke(self) -> ink::env::Result<ink::MessageResult<OpaqueTypes>> {
+        use ink::env::Error;
+        use scale::{Decode, Encode};
+
+        let flags = (self.allow_reentry as u32) << 3;
+        let callee = self.callee.encode();
+        let value = self.transferred_value.encode();
+        let mut input = self.selector.to_vec();
+        input.extend_from_slice(&self.input);
+        let mut output = vec![0u8; OUTPUT_BUFFER_SIZE];
+        let mut output_len = output.len() as u32;
+
+        let return_code = unsafe {
+            seal::call(
+                flags,
+                callee.as_ptr() as u32,
+                self.gas_limit,
+                value.as_ptr() as u32,
+                input.as_ptr() as u32,
+                input.len() as u32,
+                output.as_mut_ptr() as u32,
+                &mut output_len as *mut u32 as u32,
+            )
+        };
+        match return_code {
+            0 => {
+                output.truncate(output_len as usize);
+                ink::MessageResult::<OpaqueTypes>::decode(&mut &output[..]).map_err(Error::Decode)
+            }
+            1 => Err(Error::CalleeTrapped),
+            2 => Err(Error::CalleeReverted),
+            5 => Err(Error::TransferFailed),
+            7 => Err(Error::CodeNotFound),
+            8 => Err(Error::NotCallable),
+            _ => Err(Error::Unknown),
+        }
+    }
+
+    /// 执行交易,被调用合约回滚时返回 `Error::CalleeReverted`。
+    #[cfg(feature = "std")]
+    pub fn try_invoke(self) -> ink::env::Result<ink::MessageResult<OpaqueTypes>> {
+        self.call().try_invoke()
+    }
+}
+
+/// 被调用消息返回值的缓冲区大小,与 ink! 的静态缓冲区一致。
+#[cfg(not(feature = "std"))]
+const OUTPUT_BUFFER_SIZE: usize = 1 << 14;
+
+#[cfg(not(feature = "std"))]
+mod seal {
+    #[link(wasm_import_module = "seal1")]
+    extern "C" {
+        pub fn call(
+            flags: u32,
+            callee_ptr: u32,
+            gas: u64,
+            transferred_value_ptr: u32,
+            input_data_ptr: u32,
+            input_data_len: u32,
+            output_ptr: u32,
+            output_len_ptr: u32,
+        ) -> u32;
+    }
+}
+
+/// 从跨合约调用的结果中取出被调用消息的返回值。
+///
+/// 被调用合约回滚、panic 或返回 `LangError` 时返回 `None`,
+/// 成功时原样返回消息的返回值,不对其内容做任何假设。
+pub fn call_output(result: ink::env::Result<ink::MessageResult<OpaqueTypes>>) -> Option<Vec<u8>> {
+    match result {
+        Ok(Ok(OpaqueTypes(output))) => Some(output),
+        _ => None,
+    }
 }
 
 #[cfg_attr(
@@ -171,4 +255,29 @@ mod tests {
         test_opaque_types(vec![1, 2, 3]);
         test_opaque_types((true, "world".to_owned()));
     }
+
+    #[test]
+    fn reverted_call_fails_closed() {
+        use scale::Encode;
+
+        fn returned(value: impl Encode) -> ink::env::Result<ink::MessageResult<OpaqueTypes>> {
+            Ok(Ok(OpaqueTypes(value.encode())))
+        }
+        assert_eq!(
+            call_output(returned(Ok::<u32, u8>(7))),
+            Some(Ok::<u32, u8>(7).encode())
+        );
+        assert_eq!(call_output(returned(())), Some(vec![]));
+        // 返回值以 1 开头的消息同样成功。
+        assert_eq!(call_output(returned(true)), Some(vec![1]));
+        // 被调用消息返回 `Err` 而回滚。
+        assert_eq!(call_output(Err(ink::env::Error::CalleeReverted)), None);
+        // 选择器不存在等 `LangError`。
+        assert_eq!(
+            call_output(Ok(Err(ink::LangError::CouldNotReadInput))),
+            None
+        );
+        // 被调用合约 panic。
+        assert_eq!(call_output(Err(ink::env::Error::CalleeTrapped)), None);
+    }
 }
@@ -1,8 +1,7 @@
 use ink::env::{
-    hash::{CryptoHash, Keccak256},
+    hash::{Blake2x256, CryptoHash, Keccak256},
     Environment,
 };
-use ink::primitives::Hash;
 use scale::{Decode, Encode};
 
 use super::env::AAEnvironment;
@@ -103,3 +102,11 @@ pub fn keccak256(input: &[u8]) -> [u8; 32] {
     Keccak256::hash(input, &mut hash);
     hash
 }
+
+/// 计算一个字节数组的 Blake2x256 哈希值。
+#[inline]
+pub fn blake2_256(input: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    Blake2x256::hash(input, &mut hash);
+    hash
+}
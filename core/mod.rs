@@ -2,4 +2,6 @@ pub mod env;
 pub mod error;
 pub mod exec;
 pub mod helpers;
+pub mod signature;
+pub mod typed_data;
 pub mod user_operation;
@@ -0,0 +1,110 @@
+use ink::primitives::AccountId;
+use scale::{Decode, Encode};
+
+use super::helpers::blake2_256;
+
+/// sr25519 签名使用的签名上下文,与 Substrate 保持一致。
+const SR25519_SIGNING_CONTEXT: &[u8] = b"substrate";
+
+/// 账户所有者的签名,支持 Substrate 的三种签名算法。
+///
+/// 签名者的 `AccountId` 与 Substrate 中的推导方式相同:
+/// sr25519 和 ed25519 为公钥本身,ecdsa 为压缩公钥的 blake2_256 哈希。
+#[derive(Clone, Encode, Decode, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, core::fmt::Debug))]
+pub enum MultiSignature {
+    Sr25519([u8; 64]),
+    Ed25519([u8; 64]),
+    /// 签名的消息为 `blake2_256(message)`。
+    Ecdsa([u8; 65]),
+}
+
+impl MultiSignature {
+    /// 验证 `signer` 是否对 `message` 进行了签名。
+    pub fn verify(&self, message: &[u8], signer: &AccountId) -> bool {
+        let signer: &[u8; 32] = signer.as_ref();
+        match self {
+            MultiSignature::Sr25519(signature) => {
+                let (Ok(public), Ok(signature)) = (
+                    schnorrkel::PublicKey::from_bytes(signer),
+                    schnorrkel::Signature::from_bytes(signature),
+                ) else {
+                    return false;
+                };
+                public
+                    .verify_simple(SR25519_SIGNING_CONTEXT, message, &signature)
+                    .is_ok()
+            }
+            MultiSignature::Ed25519(signature) => {
+                let Ok(public) = ed25519_zebra::VerificationKey::try_from(*signer) else {
+                    return false;
+                };
+                public
+                    .verify(&ed25519_zebra::Signature::from(*signature), message)
+                    .is_ok()
+            }
+            MultiSignature::Ecdsa(signature) => {
+                let mut public = [0u8; 33];
+                if ink::env::ecdsa_recover(signature, &blake2_256(message), &mut public).is_err() {
+                    return false;
+                }
+                &blake2_256(&public) == signer
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &[u8] = b"user operation hash";
+
+    #[test]
+    fn verify_sr25519() {
+        let pair = schnorrkel::MiniSecretKey::from_bytes(&[1; 32])
+            .unwrap()
+            .expand_to_keypair(schnorrkel::ExpansionMode::Ed25519);
+        let signer = AccountId::from(pair.public.to_bytes());
+        let signature = MultiSignature::Sr25519(
+            pair.sign_simple(SR25519_SIGNING_CONTEXT, MESSAGE)
+                .to_bytes(),
+        );
+
+        assert!(signature.verify(MESSAGE, &signer));
+        assert!(!signature.verify(b"other message", &signer));
+        assert!(!signature.verify(MESSAGE, &AccountId::from([2; 32])));
+    }
+
+    #[test]
+    fn verify_ed25519() {
+        let pair = ed25519_zebra::SigningKey::from([1; 32]);
+        let signer = AccountId::from(<[u8; 32]>::from(ed25519_zebra::VerificationKey::from(
+            &pair,
+        )));
+        let signature = MultiSignature::Ed25519(pair.sign(MESSAGE).into());
+
+        assert!(signature.verify(MESSAGE, &signer));
+        assert!(!signature.verify(b"other message", &signer));
+        assert!(!signature.verify(MESSAGE, &AccountId::from([2; 32])));
+    }
+
+    #[test]
+    fn verify_ecdsa() {
+        use secp256k1::{Message, SecretKey, SECP256K1};
+
+        let secret = SecretKey::from_slice(&[1; 32]).unwrap();
+        let (recovery_id, compact) = SECP256K1
+            .sign_ecdsa_recoverable(&Message::from_slice(&blake2_256(MESSAGE)).unwrap(), &secret)
+            .serialize_compact();
+        let mut raw = [0u8; 65];
+        raw[..64].copy_from_slice(&compact);
+        raw[64] = recovery_id.to_i32() as u8;
+        let signer = AccountId::from(blake2_256(&secret.public_key(SECP256K1).serialize()));
+        let signature = MultiSignature::Ecdsa(raw);
+
+        assert!(signature.verify(MESSAGE, &signer));
+        assert!(!signature.verify(b"other message", &signer));
+        assert!(!signature.verify(MESSAGE, &AccountId::from([2; 32])));
+    }
+}
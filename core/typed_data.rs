@@ -0,0 +1,321 @@
+//! EIP-712 风格的结构化数据签名。
+//!
+//! 每个结构体的哈希为 `keccak256(type_hash ‖ encode_data)`,其中 `type_hash` 为类型定义字符串的
+//! keccak256,`encode_data` 将每个字段编码为 32 字节:`bytes`/`string` 取 keccak256,
+//! 整数为大端序并左侧补零,`bytesN` 右侧补零,结构体数组为各元素哈希拼接后的 keccak256。
+//! 最终签名的哈希为 `keccak256(0x19 ‖ 0x01 ‖ domain_separator ‖ struct_hash)`。
+use ink::prelude::{string::String, vec::Vec};
+use ink::primitives::{AccountId, Hash};
+
+use super::{
+    env::AAEnvironment,
+    exec::Transaction,
+    helpers::keccak256,
+    user_operation::{PaymasterAndData, UserOperation, UserOperationCall},
+};
+
+/// 可以按 EIP-712 计算哈希的结构体。
+pub trait TypedData {
+    /// 类型定义,引用的结构体类型按名称排序附加在后面。
+    const TYPE: &'static str;
+
+    /// 按字段顺序编码的 32 字节字。
+    fn encode_data(&self) -> Vec<u8>;
+
+    fn type_hash() -> [u8; 32] {
+        keccak256(Self::TYPE.as_bytes())
+    }
+
+    fn struct_hash(&self) -> [u8; 32] {
+        let mut data = Self::type_hash().to_vec();
+        data.extend(self.encode_data());
+        keccak256(&data)
+    }
+}
+
+/// 签名域,用于区分不同的应用、版本、链和 EntryPoint。
+#[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Domain {
+    /// 应用的名称。
+    pub name: String,
+    /// 签名方案的版本。
+    pub version: String,
+    /// 链的标识,与 EntryPoint 的 `get_chain_id` 一致。
+    pub chain_id: Hash,
+    /// EntryPoint 的地址。
+    pub entry_point: AccountId,
+}
+
+impl TypedData for Domain {
+    const TYPE: &'static str =
+        "Domain(string name,string version,bytes32 chainId,bytes32 entryPoint)";
+
+    fn encode_data(&self) -> Vec<u8> {
+        [
+            keccak256(self.name.as_bytes()),
+            keccak256(self.version.as_bytes()),
+            left_aligned(self.chain_id.as_ref()),
+            left_aligned(self.entry_point.as_ref()),
+        ]
+        .concat()
+    }
+}
+
+impl TypedData for Transaction<AAEnvironment> {
+    const TYPE: &'static str = "Transaction(bytes32 callee,bytes4 selector,bytes input,\
+        uint128 transferredValue,uint64 gasLimit,bool allowReentry)";
+
+    fn encode_data(&self) -> Vec<u8> {
+        [
+            left_aligned(self.callee.as_ref()),
+            left_aligned(&self.selector),
+            keccak256(&self.input),
+            uint(self.transferred_value),
+            uint(self.gas_limit.into()),
+            uint(self.allow_reentry.into()),
+        ]
+        .concat()
+    }
+}
+
+impl TypedData for UserOperation<AAEnvironment> {
+    const TYPE: &'static str = "UserOperation(bytes32 sender,bytes32 nonce,bytes initCode,\
+        bool batch,Transaction[] calls,uint64 callGasLimit,uint64 verificationGasLimit,\
+        uint64 preVerificationGas,uint64 maxFeePerGas,uint64 maxPriorityFeePerGas,\
+        bytes32 paymaster,bytes paymasterData)\
+        Transaction(bytes32 callee,bytes4 selector,bytes input,\
+        uint128 transferredValue,uint64 gasLimit,bool allowReentry)";
+
+    /// 签名不参与编码。`Call` 编码为燃料上限为 `call_gas_limit` 的单笔交易,
+    /// 并通过 `batch` 与只有一笔交易的 `Batch` 区分。
+    fn encode_data(&self) -> Vec<u8> {
+        let batch = matches!(self.call, UserOperationCall::Batch(_));
+        let calls: Vec<u8> = self
+            .transactions()
+            .iter()
+            .flat_map(TypedData::struct_hash)
+            .collect();
+        let paymaster_data: &[u8] = match &self.paymaster_and_data {
+            PaymasterAndData::OnlyPaymaster(_) => &[],
+            PaymasterAndData::PaymasterAndData { data, .. } => data,
+        };
+        [
+            left_aligned(self.sender.as_ref()),
+            self.nonce,
+            keccak256(&self.init_code),
+            uint(batch.into()),
+            keccak256(&calls),
+            uint(self.call_gas_limit.into()),
+            uint(self.verification_gas_limit.into()),
+            uint(self.pre_verification_gas.into()),
+            uint(self.max_fee_per_gas.into()),
+            uint(self.max_priority_fee_per_gas.into()),
+            left_aligned(self.paymaster_and_data.paymaster().as_ref()),
+            keccak256(paymaster_data),
+        ]
+        .concat()
+    }
+}
+
+impl UserOperation<AAEnvironment> {
+    /// 计算 `domain` 下此用户操作的结构化数据哈希。
+    pub fn typed_hash(&self, domain: &Domain) -> [u8; 32] {
+        let mut data = Vec::with_capacity(66);
+        data.extend([0x19, 0x01]);
+        data.extend(domain.struct_hash());
+        data.extend(self.struct_hash());
+        keccak256(&data)
+    }
+
+    /// 返回此用户操作的可读描述,供钱包向用户展示将要签名的内容。
+    #[cfg(feature = "std")]
+    pub fn describe(&self) -> String {
+        use core::fmt::Write;
+
+        let mut out = String::new();
+        let _ = writeln!(out, "UserOperation");
+        let _ = writeln!(out, "  sender: {}", to_hex(self.sender.as_ref()));
+        let _ = writeln!(out, "  nonce: {}", to_hex(&self.nonce));
+        match self.factory() {
+            Some(factory) => {
+                let _ = writeln!(
+                    out,
+                    "  initCode: factory {} ({} bytes)",
+                    to_hex(factory.as_ref()),
+                    self.init_code.len()
+                );
+            }
+            None => {
+                let _ = writeln!(out, "  initCode: none");
+            }
+        }
+        match &self.call {
+            UserOperationCall::Call { .. } if self.call.is_empty() => {
+                let _ = writeln!(out, "  call: none");
+            }
+            UserOperationCall::Call {
+                callee,
+                selector,
+                call_data,
+                value,
+            } => {
+                let _ = writeln!(
+                    out,
+                    "  call: {} selector {} value {} data {}",
+                    to_hex(callee.as_ref()),
+                    to_hex(selector),
+                    value,
+                    to_hex(call_data)
+                );
+            }
+            UserOperationCall::Batch(transactions) => {
+                let _ = writeln!(out, "  batch: {} calls", transactions.len());
+                for (i, tx) in transactions.iter().enumerate() {
+                    let _ = writeln!(
+                        out,
+                        "    #{i}: {} selector {} value {} data {} gasLimit {}{}",
+                        to_hex(tx.callee.as_ref()),
+                        to_hex(&tx.selector),
+                        tx.transferred_value,
+                        to_hex(&tx.input),
+                        tx.gas_limit,
+                        if tx.allow_reentry {
+                            " (allow reentry)"
+                        } else {
+                            ""
+                        }
+                    );
+                }
+            }
+        }
+        let _ = writeln!(
+            out,
+            "  gas: call {}, verification {}, preVerification {}",
+            self.call_gas_limit, self.verification_gas_limit, self.pre_verification_gas
+        );
+        let _ = writeln!(
+            out,
+            "  fee per gas: max {}, priority {}",
+            self.max_fee_per_gas, self.max_priority_fee_per_gas
+        );
+        match &self.paymaster_and_data {
+            _ if self.paymaster_and_data.is_eq_zero() => {
+                let _ = writeln!(out, "  paymaster: none");
+            }
+            PaymasterAndData::OnlyPaymaster(paymaster) => {
+                let _ = writeln!(out, "  paymaster: {}", to_hex(paymaster.as_ref()));
+            }
+            PaymasterAndData::PaymasterAndData { paymaster, data } => {
+                let _ = writeln!(
+                    out,
+                    "  paymaster: {} data {}",
+                    to_hex(paymaster.as_ref()),
+                    to_hex(data)
+                );
+            }
+        }
+        out
+    }
+}
+
+/// 大端序、左侧补零的 `uintN`。
+fn uint(value: u128) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// 右侧补零的 `bytesN`。
+fn left_aligned(bytes: &[u8]) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[..bytes.len()].copy_from_slice(bytes);
+    word
+}
+
+#[cfg(feature = "std")]
+fn to_hex(bytes: &[u8]) -> String {
+    use core::fmt::Write;
+
+    let mut out = String::from("0x");
+    for byte in bytes {
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ink::prelude::vec;
+
+    fn domain() -> Domain {
+        Domain {
+            name: "ink-aa".into(),
+            version: "1".into(),
+            chain_id: Hash::from([1; 32]),
+            entry_point: AccountId::from([2; 32]),
+        }
+    }
+
+    fn op() -> UserOperation {
+        UserOperation {
+            sender: AccountId::from([3; 32]),
+            call: UserOperationCall::Call {
+                callee: AccountId::from([4; 32]),
+                selector: [0xde, 0xad, 0xbe, 0xef],
+                call_data: vec![1, 2, 3],
+                value: 10,
+            },
+            call_gas_limit: 100,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn struct_hash_follows_type_definition() {
+        let domain = domain();
+        let mut data = keccak256(Domain::TYPE.as_bytes()).to_vec();
+        data.extend(keccak256(b"ink-aa"));
+        data.extend(keccak256(b"1"));
+        data.extend([1; 32]);
+        data.extend([2; 32]);
+        assert_eq!(domain.struct_hash(), keccak256(&data));
+
+        assert_eq!(uint(258)[30..], [1, 2]);
+        assert_eq!(left_aligned(&[0xde, 0xad])[..3], [0xde, 0xad, 0]);
+    }
+
+    #[test]
+    fn typed_hash_covers_domain_and_op_but_not_signature() {
+        let op = op();
+        let hash = op.typed_hash(&domain());
+
+        let signed = UserOperation {
+            signature: vec![9; 64],
+            ..op.clone()
+        };
+        assert_eq!(signed.typed_hash(&domain()), hash);
+
+        let other_chain = Domain {
+            chain_id: Hash::from([5; 32]),
+            ..domain()
+        };
+        assert_ne!(op.typed_hash(&other_chain), hash);
+
+        // 同一笔交易作为 `Batch` 提交时哈希不同。
+        let batch = UserOperation {
+            call: UserOperationCall::Batch(op.transactions()),
+            ..op.clone()
+        };
+        assert_ne!(batch.typed_hash(&domain()), hash);
+    }
+
+    #[test]
+    fn describe_shows_call() {
+        let description = op().describe();
+        assert!(description.contains(&format!("sender: 0x{}", "03".repeat(32))));
+        assert!(description.contains("selector 0xdeadbeef value 10 data 0x010203"));
+        assert!(description.contains("paymaster: none"));
+    }
+}
@@ -2,32 +2,29 @@ use ink::{
     env::Environment,
     primitives::{AccountId, Hash},
 };
-use scale::Encode;
+use scale::{Decode, Encode};
 
-use crate::traits::paymaster;
-
-use super::{env::AAEnvironment, helpers::keccak256};
+use super::{env::AAEnvironment, exec::Transaction, helpers::keccak256};
 use ink::prelude::vec::Vec;
 
 /// `UserOperation` 结构体定义了一个用户操作。
-#[derive(scale::Encode, scale::Decode, Clone, Hash, Debug)]
+#[derive(scale::Encode, scale::Decode, Clone, Debug)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-pub struct UserOperation<E: Environment = AAEnvironment> {
+pub struct UserOperation<E: Environment = AAEnvironment>
+where
+    E::Balance: core::fmt::Debug,
+{
     /// 发送人的账户 ID。
     /// 必须是钱包，即实现了IAccount接口的合约地址
     pub sender: E::AccountId,
     /// 用户操作的随机数。
     /// 目前不影响最终结果
     pub nonce: [u8; 32],
-    /// 要在合约上创建的代码的字节数组。
-    /// 目前不影响最终结果
+    /// 用于创建账户的数据,由工厂地址和调用工厂的数据组成。
+    /// 仅当账户尚未部署时才需要,否则必须为空。
     pub init_code: Vec<u8>,
-    /// 要调用的合约地址。
-    pub callee: E::AccountId,
-    /// 要调用的合约方法。
-    pub selector: [u8; 4],
-    /// 要调用的合约参数。
-    pub call_data: Vec<u8>,
+    /// 要执行的调用。
+    pub call: UserOperationCall<E>,
     /// 调用此用户操作时可用的燃料量。
     pub call_gas_limit: u64,
     /// 用于验证此用户操作的燃料量。
@@ -41,10 +38,47 @@ pub struct UserOperation<E: Environment = AAEnvironment> {
     /// 付款人的地址和数据。
     pub paymaster_and_data: PaymasterAndData<E>,
     /// 用户操作的签名。
-    /// 目前不影响最终结果
+    /// 格式由账户的验证器决定,例如 `recover_sig` 为 SCALE 编码的
+    /// `Vec<(AccountId, MultiSignature)>`。
     pub signature: Vec<u8>,
 }
 
+/// 用户操作要执行的调用。
+#[derive(scale::Encode, scale::Decode, Clone, Debug)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum UserOperationCall<E: Environment = AAEnvironment>
+where
+    E::Balance: core::fmt::Debug,
+{
+    /// 调用一个合约方法。`callee` 为零地址时不执行调用。
+    Call {
+        /// 要调用的合约地址。
+        callee: E::AccountId,
+        /// 要调用的合约方法。
+        selector: [u8; 4],
+        /// 要调用的合约参数。
+        call_data: Vec<u8>,
+        /// 由账户从自己的余额中转给 `callee` 的金额。
+        value: E::Balance,
+    },
+    /// 通过账户的 `execute_batch` 依次执行一批交易,
+    /// 任何一笔交易失败都会回滚整批交易。
+    Batch(Vec<Transaction<AAEnvironment>>),
+}
+
+impl<E: Environment> UserOperationCall<E>
+where
+    E::Balance: core::fmt::Debug,
+{
+    /// 是否没有需要执行的调用。
+    pub fn is_empty(&self) -> bool {
+        match self {
+            UserOperationCall::Call { callee, .. } => callee.as_ref().iter().all(|n| 0.eq(n)),
+            UserOperationCall::Batch(transactions) => transactions.is_empty(),
+        }
+    }
+}
+
 #[derive(scale::Encode, scale::Decode, Clone, Hash, Debug)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 pub enum PaymasterAndData<E: Environment> {
@@ -96,9 +130,12 @@ impl Default for UserOperation<AAEnvironment> {
             sender: AccountId::from([0; 32]),
             nonce: Default::default(),
             init_code: Default::default(),
-            callee: AccountId::from([0; 32]),
-            selector: Default::default(),
-            call_data: Default::default(),
+            call: UserOperationCall::Call {
+                callee: AccountId::from([0; 32]),
+                selector: Default::default(),
+                call_data: Default::default(),
+                value: Default::default(),
+            },
             call_gas_limit: Default::default(),
             verification_gas_limit: Default::default(),
             pre_verification_gas: Default::default(),
@@ -110,7 +147,33 @@ impl Default for UserOperation<AAEnvironment> {
     }
 }
 
-impl<E: Environment> UserOperation<E> {
+impl UserOperation<AAEnvironment> {
+    /// 返回此用户操作要执行的交易。
+    /// `Call` 转换为燃料上限为 `call_gas_limit` 的交易。
+    pub fn transactions(&self) -> Vec<Transaction> {
+        match &self.call {
+            UserOperationCall::Call { .. } if self.call.is_empty() => Vec::new(),
+            UserOperationCall::Call {
+                callee,
+                selector,
+                call_data,
+                value,
+            } => ink::prelude::vec![Transaction::new(
+                *callee,
+                *selector,
+                call_data.clone(),
+                *value,
+                self.call_gas_limit,
+            )],
+            UserOperationCall::Batch(transactions) => transactions.clone(),
+        }
+    }
+}
+
+impl<E: Environment> UserOperation<E>
+where
+    E::Balance: core::fmt::Debug,
+{
     /// 计算用户操作的燃料价格。
     pub fn gas_price(&self) -> u64 {
         let max_fee_per_gas = self.max_fee_per_gas;
@@ -123,13 +186,29 @@ impl<E: Environment> UserOperation<E> {
         }
     }
 
+    /// 计算执行此用户操作需要预付的最大费用。
+    /// 没有 paymaster 时,验证燃料需要覆盖 postOp 等额外开销,按三倍计算。
+    /// 溢出时返回 `None`。
+    pub fn required_prefund(&self) -> Option<u64> {
+        let mul = if self.paymaster_and_data.is_eq_zero() {
+            3
+        } else {
+            1
+        };
+        self.verification_gas_limit
+            .checked_mul(mul)
+            .and_then(|pre| pre.checked_add(self.call_gas_limit))
+            .and_then(|pre| pre.checked_add(self.pre_verification_gas))
+            .and_then(|gas| gas.checked_mul(self.max_fee_per_gas))
+    }
+
     /// 打包一个 `EnvUserOperation` 为字节数组。
     pub fn pack(&self) -> Vec<u8> {
         UserOperationPack::<E> {
             sender: self.sender.clone(),
             nonce: self.nonce,
             init_code: keccak256(&self.init_code).into(),
-            call_data: keccak256(&self.call_data).into(),
+            call_data: keccak256(&self.call.encode()).into(),
             call_gas_limit: self.call_gas_limit,
             verification_gas_limit: self.verification_gas_limit,
             pre_verification_gas: self.pre_verification_gas,
@@ -144,6 +223,20 @@ impl<E: Environment> UserOperation<E> {
     pub fn hash(&self) -> [u8; 32] {
         keccak256(&Self::pack(self))
     }
+
+    /// 计算 EntryPoint 的 `get_user_op_hash`,即账户需要签名的哈希。
+    ///
+    /// - `entry_point` EntryPoint 的地址
+    /// - `chain_id` EntryPoint 构造时设置的链标识(创世区块哈希)
+    pub fn user_op_hash(&self, entry_point: &E::AccountId, chain_id: &Hash) -> [u8; 32] {
+        keccak256(&(self.hash(), entry_point, chain_id).encode())
+    }
+
+    /// 从 `init_code` 中取出工厂地址。
+    /// 如果 `init_code` 的长度不足一个账户地址，则返回 `None`。
+    pub fn factory(&self) -> Option<E::AccountId> {
+        E::AccountId::decode(&mut &self.init_code[..]).ok()
+    }
 }
 
 /// `UserOperationPack` 结构体定义了一个打包了用户操作的结构体。
@@ -160,3 +253,23 @@ struct UserOperationPack<E: Environment> {
     max_priority_fee_per_gas: u64,
     paymaster_and_data: Hash,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_op_hash_is_domain_separated() {
+        let op: UserOperation = UserOperation::default();
+        let entry_point = AccountId::from([1; 32]);
+        let chain_id = Hash::from([2; 32]);
+
+        let hash = op.user_op_hash(&entry_point, &chain_id);
+        assert_eq!(
+            hash,
+            keccak256(&(op.hash(), entry_point, chain_id).encode())
+        );
+        assert_ne!(hash, op.user_op_hash(&AccountId::from([3; 32]), &chain_id));
+        assert_ne!(hash, op.user_op_hash(&entry_point, &Hash::from([4; 32])));
+    }
+}
@@ -1,6 +1,9 @@
 use crate::core::error::Result;
-use crate::core::{env::AAEnvironment, helpers::ValidationData, user_operation::UserOperation};
+use crate::core::{
+    env::AAEnvironment, exec::Transaction, helpers::ValidationData, user_operation::UserOperation,
+};
 use ink::env::Environment;
+use ink::prelude::vec::Vec;
 
 /// `IAccount` trait 定义了一个账户的接口。
 #[ink::trait_definition]
@@ -44,5 +47,60 @@ pub trait IAccount {
         missing_account_funds: Balance<AAEnvironment>,
     ) -> Result<ValidationData<AAEnvironment>>;
 }
+/// `IAccountExecute` trait 定义了账户执行交易的接口。
+///
+/// EntryPoint 通过它让账户发起调用,被调用的合约看到的 `caller()` 是账户本身。
+#[ink::trait_definition]
+pub trait IAccountExecute {
+    /// 以账户的身份调用 `callee` 的 `selector` 方法,返回调用的返回值。
+    ///
+    /// 调用失败返回 `Error::ExecutionFailed`。
+    /// 必须验证调用者是 `entryPoint`。
+    ///
+    /// # Arguments
+    ///
+    /// * `callee` - 被调用的合约。
+    /// * `selector` - 被调用方法的选择器。
+    /// * `input` - SCALE 编码的调用参数。
+    /// * `value` - 从账户余额中转给 `callee` 的金额。
+    #[ink(message)]
+    fn execute(
+        &mut self,
+        callee: AccountId<AAEnvironment>,
+        selector: [u8; 4],
+        input: Vec<u8>,
+        value: Balance<AAEnvironment>,
+    ) -> Result<Vec<u8>>;
+
+    /// 依次执行一批交易,返回每笔交易的返回值。
+    ///
+    /// 任何一笔交易失败都返回 `Error::ExecutionFailed`,整批交易被回滚。
+    /// 必须验证调用者是 `entryPoint`。
+    ///
+    /// # Arguments
+    ///
+    /// * `transactions` - 要执行的交易。
+    #[ink(message)]
+    fn execute_batch(
+        &mut self,
+        transactions: Vec<Transaction<AAEnvironment>>,
+    ) -> Result<Vec<Vec<u8>>>;
+}
+
+/// `IAggregatedAccount` trait 定义了使用签名聚合器的账户的接口。
+///
+/// 聚合器通过它获取账户的公钥,以验证一批用户操作的聚合签名。
+#[ink::trait_definition]
+pub trait IAggregatedAccount {
+    /// 返回账户使用的签名聚合器。
+    #[ink(message)]
+    fn get_aggregator(&self) -> AccountId<AAEnvironment>;
+
+    /// 返回账户在聚合器中注册的公钥,格式由聚合器决定。
+    #[ink(message)]
+    fn get_public_key(&self) -> Vec<u8>;
+}
+
+type AccountId<E> = <E as Environment>::AccountId;
 type Hash<E> = <E as Environment>::Hash;
 type Balance<E> = <E as Environment>::Balance;
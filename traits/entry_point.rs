@@ -8,19 +8,28 @@ use ink::env::Environment;
 use ink::prelude::vec::Vec;
 use scale::{Decode, Encode};
 
-use super::{account::IAccount, nonce_manager::INonceManager, paymaster::IPaymaster};
+use super::{
+    account::{IAccount, IAccountExecute, IAggregatedAccount},
+    nonce_manager::INonceManager,
+    paymaster::IPaymaster,
+};
 
 pub type AggregatorRef<E> = <<ink::reflect::TraitDefinitionRegistry<E> as IAggregator> ::__ink_TraitInfo as TraitCallForwarder>::Forwarder;
 pub type PaymasterRef<E> = <<ink::reflect::TraitDefinitionRegistry<E> as IPaymaster> ::__ink_TraitInfo as TraitCallForwarder>::Forwarder;
 pub type EntryPointRef<E> = <<ink::reflect::TraitDefinitionRegistry<E> as IEntryPoint> ::__ink_TraitInfo as TraitCallForwarder>::Forwarder;
 pub type StakeManagerRef<E> = <<ink::reflect::TraitDefinitionRegistry<E> as IStakeManager> ::__ink_TraitInfo as TraitCallForwarder>::Forwarder;
 pub type AccountRef<E> = <<ink::reflect::TraitDefinitionRegistry<E> as IAccount> ::__ink_TraitInfo as TraitCallForwarder>::Forwarder;
+pub type AccountExecuteRef<E> = <<ink::reflect::TraitDefinitionRegistry<E> as IAccountExecute> ::__ink_TraitInfo as TraitCallForwarder>::Forwarder;
+pub type AggregatedAccountRef<E> = <<ink::reflect::TraitDefinitionRegistry<E> as IAggregatedAccount> ::__ink_TraitInfo as TraitCallForwarder>::Forwarder;
 pub type NonceManagerRef<E> = <<ink::reflect::TraitDefinitionRegistry<E> as INonceManager> ::__ink_TraitInfo as TraitCallForwarder>::Forwarder;
 
 /// 为每个聚合器处理的 UserOps     
 #[derive(Encode, Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-pub struct UserOpsPerAggregator<E: Environment = AAEnvironment> {
+pub struct UserOpsPerAggregator<E: Environment = AAEnvironment>
+where
+    E::Balance: core::fmt::Debug,
+{
     /// 用户操作            
     pub user_ops: Vec<UserOperation<E>>,
 
@@ -114,6 +123,36 @@ pub trait IEntryPoint {
         ops_per_aggregator: Vec<UserOpsPerAggregator<AAEnvironment>>,
         beneficiary: <AAEnvironment as Environment>::AccountId,
     ) -> Result<()>;
+    /// 模拟调用 account.validateUserOp 和 paymaster.validatePaymasterUserOp。
+    /// 此方法总是回滚。
+    /// 成功时返回 `Error::ValidationResult`,
+    /// 如果账户需要签名聚合器,则返回 `Error::ValidationResultWithAggregation`。
+    /// 其他错误表示验证失败。
+    ///
+    /// - `user_op` 要验证的用户操作
+    #[ink(message)]
+    fn simulate_validation(&mut self, user_op: UserOperation<AAEnvironment>) -> Result<()>;
+    /// 模拟执行单个 UserOperation,包括验证和目标调用。
+    /// 此方法总是回滚,成功时返回 `Error::ExecutionResult`。
+    /// 可以用于估算 `call_gas_limit`,以及预览调用的返回值。
+    ///
+    /// - `op` 要模拟的用户操作
+    /// - `target` 模拟执行后额外调用的合约地址,地址(0) 表示不调用
+    /// - `target_call_data` 调用 `target` 的数据(选择器 + 参数)
+    #[ink(message)]
+    fn simulate_handle_op(
+        &mut self,
+        op: UserOperation<AAEnvironment>,
+        target: <AAEnvironment as Environment>::AccountId,
+        target_call_data: Vec<u8>,
+    ) -> Result<()>;
+    /// 获取账户的反事实地址。
+    /// 通过 sender_creator 使用 `init_code` 创建账户,此方法总是回滚,
+    /// 并通过 `Error::SenderAddressResult` 返回账户地址。
+    ///
+    /// - `init_code` 工厂地址和调用工厂的数据
+    #[ink(message)]
+    fn get_sender_address(&mut self, init_code: Vec<u8>) -> Result<()>;
     /// 生成请求 ID  - 该请求的唯一标识符。   
     ///  请求 ID 是 userOp 的内容(除签名外)、入口点以及链 ID 的哈希。
     #[ink(message)]
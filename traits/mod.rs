@@ -3,5 +3,6 @@ pub mod aggregator;
 pub mod entry_point;
 pub mod nonce_manager;
 pub mod paymaster;
+pub mod psp22;
 pub mod sender_creator;
 pub mod stake_manager;
@@ -1,3 +1,4 @@
+use crate::core::error::Result;
 use ink::env::Environment;
 
 /// INonceManager trait定义了管理nonce（一次性密码）的方法。
@@ -29,6 +30,10 @@ pub trait INonceManager {
     /// # 参数
     ///
     /// * `key` - 高192位的nonce
+    ///
+    /// # 返回值
+    ///
+    /// 转发给 NonceManager 失败时返回错误。
     #[ink(message)]
-    fn increment_nonce(&mut self, key: [u8; 24]);
+    fn increment_nonce(&mut self, key: [u8; 24]) -> Result<()>;
 }
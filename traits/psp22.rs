@@ -0,0 +1,78 @@
+use ink::env::Environment;
+use ink::prelude::{string::String, vec::Vec};
+
+use crate::core::env::AAEnvironment;
+
+type AccountId = <AAEnvironment as Environment>::AccountId;
+type Balance = <AAEnvironment as Environment>::Balance;
+
+/// `PSP22::transfer` 的选择器。
+pub const TRANSFER_SELECTOR: [u8; 4] = ink::selector_bytes!("PSP22::transfer");
+/// `PSP22::transfer_from` 的选择器。
+pub const TRANSFER_FROM_SELECTOR: [u8; 4] = ink::selector_bytes!("PSP22::transfer_from");
+/// `PSP22::approve` 的选择器。
+pub const APPROVE_SELECTOR: [u8; 4] = ink::selector_bytes!("PSP22::approve");
+/// `PSP22::increase_allowance` 的选择器。
+pub const INCREASE_ALLOWANCE_SELECTOR: [u8; 4] = ink::selector_bytes!("PSP22::increase_allowance");
+
+/// PSP22 代币操作的错误。
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22Error {
+    /// 自定义错误。
+    Custom(String),
+    /// 余额不足。
+    InsufficientBalance,
+    /// 授权额度不足。
+    InsufficientAllowance,
+    /// 接收者地址为零地址。
+    ZeroRecipientAddress,
+    /// 发送者地址为零地址。
+    ZeroSenderAddress,
+    /// 安全转账检查失败。
+    SafeTransferCheckFailed(String),
+}
+
+/// PSP22 同质化代币标准(Polkadot 上的 ERC-20)。
+///
+/// 只包含账户和 paymaster 需要用到的方法,选择器与标准一致。
+#[ink::trait_definition]
+pub trait PSP22 {
+    /// 返回代币的总供应量。
+    #[ink(message)]
+    fn total_supply(&self) -> Balance;
+
+    /// 返回 `owner` 的余额。
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> Balance;
+
+    /// 返回 `spender` 可以从 `owner` 转出的数量。
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance;
+
+    /// 从调用者向 `to` 转账 `value`。
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    /// 使用调用者的授权额度从 `from` 向 `to` 转账 `value`。
+    #[ink(message)]
+    fn transfer_from(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        value: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error>;
+
+    /// 允许 `spender` 从调用者转出最多 `value`。
+    #[ink(message)]
+    fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error>;
+
+    /// 将 `spender` 可以从调用者转出的数量增加 `delta_value`。
+    #[ink(message)]
+    fn increase_allowance(
+        &mut self,
+        spender: AccountId,
+        delta_value: Balance,
+    ) -> Result<(), PSP22Error>;
+}
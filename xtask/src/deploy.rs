@@ -19,6 +19,8 @@ impl CommandGenerator {
             storage_deposit_limit,
             password,
             directory,
+            // 只作为 EntryPoint 构造函数的参数,见 `deploy_entry_point`。
+            chain_id: _,
         } = deploy;
 
         let url = url.as_ref().map(|u| format!("--url {u}"));
@@ -94,6 +96,9 @@ pub fn deploy_entry_point(deploy: &Deploy) -> anyhow::Result<String> {
     println!("upload nonce manager:");
     let nonce_manager_code_hash = code_hash("nonce_manager.contract")?;
     println!("nonce manager code hash: {}", nonce_manager_code_hash);
+    println!("upload sender creator:");
+    let sender_creator_code_hash = code_hash("sender_creator.contract")?;
+    println!("sender creator code hash: {}", sender_creator_code_hash);
 
     sleep(std::time::Duration::from_secs(5));
 
@@ -108,6 +113,8 @@ pub fn deploy_entry_point(deploy: &Deploy) -> anyhow::Result<String> {
         1u32.to_string(),
         stake_manager_code_hash,
         nonce_manager_code_hash,
+        sender_creator_code_hash,
+        deploy.chain_id.clone(),
     ];
     let entry_point_address = contract_address("entry_point.contract", None, &entry_point_args)?;
 
@@ -23,6 +23,8 @@ xflags::xflags! {
         cmd deploy {
             /// 必选参数，指定部署合约的账户的密钥 URI
             required -s, --suri suri: PathBuf
+            /// 必选参数，指定所在链的创世区块哈希（0x 开头的十六进制），作为 EntryPoint 的链标识
+            required --chain-id chain_id: String
             /// 可选参数，指定输出目录，默认为 ./output
             optional directory: PathBuf
             /// 可选参数，指定 substrate 节点的 Websockets URL，默认为 ws://localhost:9944
@@ -63,6 +65,7 @@ pub struct Deploy {
     pub directory: Option<PathBuf>,
 
     pub suri: PathBuf,
+    pub chain_id: String,
     pub url: Option<String>,
     pub storage_deposit_limit: Option<u128>,
     pub password: Option<String>,